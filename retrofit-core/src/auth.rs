@@ -0,0 +1,101 @@
+//! The credentials of the outgoing requests.
+use http::{header::AUTHORIZATION, HeaderValue};
+
+use crate::{
+    backend::{Backend, BoxFuture, Request},
+    Error, Result,
+};
+
+/// The credentials of the `Authorization` header.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Credentials {
+    /// The bearer token, e.g. an OAuth2 access token.
+    Bearer(String),
+    /// The username and optional password.
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+}
+
+impl Credentials {
+    /// Returns the value of the `Authorization` header, which is marked as sensitive.
+    pub fn header_value(&self) -> Result<HeaderValue> {
+        let value = match self {
+            Credentials::Bearer(token) => format!("Bearer {}", token),
+            Credentials::Basic { username, password } => format!(
+                "Basic {}",
+                base64::encode(format!(
+                    "{}:{}",
+                    username,
+                    password.as_deref().unwrap_or_default()
+                ))
+            ),
+        };
+        let mut value = HeaderValue::from_str(&value).map_err(|err| Error::Request(err.into()))?;
+
+        value.set_sensitive(true);
+
+        Ok(value)
+    }
+}
+
+/// Supplies the credentials of every request at request time, declared with `#[auth(...)]`.
+///
+/// When a request is rejected with `401 Unauthorized`, the provider could refresh the credentials,
+/// e.g. an expired OAuth2 access token, then the request is replayed once with the new credentials.
+///
+/// The async client calls `async_credentials` and `async_refresh`, which call the blocking methods
+/// by default. A provider which fetches the credentials over the network, e.g. with an async client,
+/// should override them instead of blocking the executor.
+pub trait CredentialProvider: Send + Sync {
+    /// Returns the current credentials.
+    fn credentials(&self) -> Result<Credentials>;
+
+    /// Refreshes the credentials after a `401 Unauthorized` response,
+    /// returns `true` if the request should be replayed.
+    fn refresh(&self) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Returns the current credentials for the async client.
+    fn async_credentials(&self) -> BoxFuture<'_, Result<Credentials>> {
+        Box::pin(async move { self.credentials() })
+    }
+
+    /// Refreshes the credentials for the async client after a `401 Unauthorized` response,
+    /// returns `true` if the request should be replayed.
+    fn async_refresh(&self) -> BoxFuture<'_, Result<bool>> {
+        Box::pin(async move { self.refresh() })
+    }
+}
+
+impl CredentialProvider for Credentials {
+    fn credentials(&self) -> Result<Credentials> {
+        Ok(self.clone())
+    }
+}
+
+/// Sets the `Authorization` header of the request with the current credentials.
+pub(crate) fn authorize<B: Backend, R: Request<B>>(
+    provider: &dyn CredentialProvider,
+    req: &mut R,
+) -> Result<()> {
+    let value = provider.credentials()?.header_value()?;
+
+    req.headers_mut().insert(AUTHORIZATION, value);
+
+    Ok(())
+}
+
+/// Sets the `Authorization` header of the request with the current credentials of the async client.
+pub(crate) async fn async_authorize<B: Backend, R: Request<B>>(
+    provider: &dyn CredentialProvider,
+    req: &mut R,
+) -> Result<()> {
+    let value = provider.async_credentials().await?.header_value()?;
+
+    req.headers_mut().insert(AUTHORIZATION, value);
+
+    Ok(())
+}
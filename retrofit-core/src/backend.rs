@@ -0,0 +1,500 @@
+//! The HTTP backend used by the generated services.
+//!
+//! A backend crate, e.g. `retrofit-reqwest`, defines a marker type implementing `Backend`,
+//! implements these traits for its async types and the types in its `blocking` module,
+//! and exports them with the same names:
+//!
+//! - `Client`, `ClientBuilder`, `Request`, `Response`, `Body` and `multipart::Form`
+//! - `blocking::{Client, ClientBuilder, Request, Response, Body, multipart::Form}`
+//! - `Backend`, the marker type of the backend
+//! - `Method`, `HeaderMap` and `HeaderValue`
+//! - `Paginate` and `blocking::Paginate`, the `paginate::Pages` of its clients
+//! - `Download` and `blocking::Download`, the `download::Download` of its responses
+//! - `Ndjson<T>` and `blocking::Ndjson<T>`, the `ndjson::Ndjson` of its responses
+//!
+//! The `retrofit` crate re-exports the backend selected by cargo feature,
+//! so the generated code never names a backend directly.
+use std::error::Error as StdError;
+use std::fmt::Display;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::{HeaderMap, Method, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    auth::{async_authorize, authorize},
+    convert::{self, Converters},
+    interceptor::{intercept_request, intercept_response},
+    CredentialProvider, Error, Interceptor, Result, Retry, StatusError,
+};
+
+/// An owned dynamically typed `Future` returned by the async backend.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// The marker type of a HTTP backend.
+pub trait Backend {
+    type Error: StdError + Send + Sync + 'static;
+
+    /// Converts the backend error into an `Error`.
+    fn error(err: Self::Error) -> Error;
+
+    /// Waits until the duration has elapsed, on the runtime of the async client.
+    fn sleep(duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// A builder to configure a `Client`.
+pub trait ClientBuilder<B: Backend>: Sized {
+    type Client: Client<B>;
+
+    /// Sets the `User-Agent` header to be used by the client.
+    fn user_agent(self, value: &'static str) -> Self;
+
+    /// Sets the default headers for every request.
+    fn default_headers(self, headers: HeaderMap) -> Self;
+
+    /// Returns a `Client` that uses this `ClientBuilder` configuration.
+    fn build(self) -> Result<Self::Client, B::Error>;
+}
+
+/// A client to make requests with.
+pub trait Client<B: Backend> {
+    type RequestBuilder: RequestBuilder<B>;
+
+    /// Start building a `Request` with the `Method` and `Url`.
+    fn request(&self, method: Method, url: &str) -> Self::RequestBuilder;
+}
+
+/// A builder to construct the properties of a `Request`.
+pub trait RequestBuilder<B: Backend>: Sized {
+    type Body;
+    type Form;
+    type Request: Request<B>;
+
+    /// Build a `Request`, which can be inspected, modified and executed with the client.
+    fn build(self) -> Result<Self::Request, B::Error>;
+
+    /// Attempt to clone the builder, returns `None` if the body is a stream.
+    fn try_clone(&self) -> Option<Self>;
+
+    /// Add a header to this request.
+    fn header(self, name: &str, value: &str) -> Self;
+
+    /// Add a set of headers to the existing ones on this request.
+    fn headers(self, headers: HeaderMap) -> Self;
+
+    /// Modify the query string of the URL.
+    fn query<T: Serialize + ?Sized>(self, query: &T) -> Self;
+
+    /// Send a form body.
+    fn form<T: Serialize + ?Sized>(self, form: &T) -> Self;
+
+    /// Send a JSON body.
+    fn json<T: Serialize + ?Sized>(self, json: &T) -> Self;
+
+    /// Set the request body.
+    fn body<T: Into<Self::Body>>(self, body: T) -> Self;
+
+    /// Sends a multipart/form-data body.
+    fn multipart(self, form: Self::Form) -> Self;
+}
+
+/// A `multipart/form-data` body, built from the `#[part("name")]` arguments.
+pub trait Form<B: Backend>: Sized {
+    /// Creates an empty form.
+    fn new() -> Self;
+
+    /// Adds a text part with the formatted value.
+    fn text<T: Display + ?Sized>(self, name: &str, value: &T) -> Self;
+
+    /// Adds a binary part of `application/octet-stream`.
+    fn bytes<T: AsRef<[u8]> + ?Sized>(self, name: &str, value: &T) -> Self;
+
+    /// Adds a part streamed from the file, with the file name of the file.
+    ///
+    /// The file is opened and its length is read on the calling thread, even for an async form,
+    /// since the form is built synchronously.
+    fn file<P: AsRef<Path> + ?Sized>(self, name: &str, path: &P) -> Result<Self>;
+
+    /// Adds a part of `application/json` with the JSON serialization of the value.
+    fn json<T: Serialize + ?Sized>(self, name: &str, value: &T) -> Result<Self>;
+}
+
+/// A request which can be executed with the client.
+pub trait Request<B: Backend>: Sized {
+    /// Get the method.
+    fn method(&self) -> &Method;
+
+    /// Get a mutable reference to the method.
+    fn method_mut(&mut self) -> &mut Method;
+
+    /// Get the url.
+    fn url(&self) -> &str;
+
+    /// Set the url.
+    fn set_url(&mut self, url: &str) -> Result<()>;
+
+    /// Get a mutable reference to the headers.
+    fn headers_mut(&mut self) -> &mut HeaderMap;
+
+    /// Attempt to clone the request, returns `None` if the body is a stream.
+    fn try_clone(&self) -> Option<Self>;
+}
+
+/// A response to a submitted `Request`.
+pub trait Response<B: Backend> {
+    /// Get the `StatusCode` of this `Response`.
+    fn status(&self) -> StatusCode;
+
+    /// Get the `Headers` of this `Response`.
+    fn headers(&self) -> &HeaderMap;
+
+    /// Get a mutable reference to the `Headers` of this `Response`.
+    fn headers_mut(&mut self) -> &mut HeaderMap;
+
+    /// Get the final `Url` of this `Response`.
+    fn url(&self) -> &str;
+}
+
+pub mod blocking {
+    //! The blocking backend.
+    use super::Response as _;
+    use super::*;
+
+    /// A blocking client to execute the built requests with.
+    pub trait Client<B: Backend> {
+        type Request: Request<B>;
+        type Response: Response<B>;
+
+        /// Executes a `Request`.
+        fn execute(&self, req: Self::Request) -> Result<Self::Response, B::Error>;
+    }
+
+    /// A builder to construct and send a blocking request.
+    pub trait RequestBuilder<B: Backend>: super::RequestBuilder<B> {
+        type Response: Response<B>;
+
+        /// Streams the request body from a blocking `Upload`.
+        fn upload(self, body: crate::upload::blocking::Upload) -> Self;
+
+        /// Constructs the `Request` and sends it the target URL, returning a `Response`.
+        fn send(self) -> Result<Self::Response, B::Error>;
+    }
+
+    /// A response to a submitted blocking `Request`.
+    pub trait Response<B: Backend>: super::Response<B> + Sized {
+        /// Try and deserialize the response body as JSON.
+        fn json<T: DeserializeOwned>(self) -> Result<T, B::Error>;
+
+        /// Get the response text.
+        fn text(self) -> Result<String, B::Error>;
+
+        /// Get the response text given a specific encoding.
+        fn text_with_charset(self, default_encoding: &str) -> Result<String, B::Error>;
+
+        /// Get the full response body as `Bytes`.
+        fn bytes(self) -> Result<Bytes, B::Error>;
+    }
+
+    /// Executes the request with the credentials, interceptors and retry policy,
+    /// and turns a non-success response into `Error::Status`.
+    ///
+    /// A request rejected with `401 Unauthorized` is replayed once if the credentials were refreshed.
+    pub fn execute<B: Backend, C: Client<B>>(
+        client: &C,
+        req: Result<C::Request, B::Error>,
+        interceptors: &[Arc<dyn Interceptor>],
+        credentials: Option<&dyn CredentialProvider>,
+        retry: Option<&Retry>,
+    ) -> Result<C::Response> {
+        let mut req = req.map_err(B::error)?;
+        let send = |mut req: C::Request| {
+            if interceptors.is_empty() {
+                return client.execute(req).map_err(B::error);
+            }
+
+            let extensions = intercept_request(interceptors, &mut req)?;
+            let mut res = client.execute(req).map_err(B::error)?;
+
+            intercept_response(interceptors, &mut res, extensions)?;
+
+            Ok(res)
+        };
+        let send = |req: C::Request| match retry {
+            Some(retry) => retry.blocking_send::<B, _, _>(req, C::Request::try_clone, &send),
+            None => send(req),
+        };
+        let res = match credentials {
+            Some(provider) => {
+                authorize(provider, &mut req)?;
+
+                let replay = req.try_clone();
+                let res = send(req)?;
+
+                match replay {
+                    Some(mut req)
+                        if res.status() == StatusCode::UNAUTHORIZED && provider.refresh()? =>
+                    {
+                        tracing::debug!("replay request with the refreshed credentials");
+
+                        authorize(provider, &mut req)?;
+                        send(req)?
+                    }
+                    _ => res,
+                }
+            }
+            None => send(req)?,
+        };
+
+        error_for_status(res)
+    }
+
+    /// Turns a non-success response into `Error::Status` with the status code, headers and raw body.
+    pub fn error_for_status<B: Backend, R: Response<B>>(res: R) -> Result<R> {
+        let status = res.status();
+
+        if status.is_client_error() || status.is_server_error() {
+            let headers = res.headers().clone();
+            let body = res.bytes().map_err(B::error)?;
+
+            Err(Error::Status(Box::new(StatusError {
+                status,
+                headers,
+                body,
+            })))
+        } else {
+            Ok(res)
+        }
+    }
+
+    /// Decodes the response body with the converter of its `Content-Type`.
+    pub fn decode<B: Backend, R: Response<B>, T: DeserializeOwned>(
+        res: R,
+        converters: &Converters,
+    ) -> Result<T> {
+        let headers = res.headers().clone();
+        let body = res.bytes().map_err(B::error)?;
+
+        converters.decode(&headers, &body)
+    }
+
+    /// Decodes the response body with the converter of the media type,
+    /// regardless of the response `Content-Type`.
+    pub fn decode_as<B: Backend, R: Response<B>, T: DeserializeOwned>(
+        res: R,
+        converters: &Converters,
+        media_type: &str,
+    ) -> Result<T> {
+        let converter = converters.decoder(media_type)?;
+        let body = res.bytes().map_err(B::error)?;
+
+        convert::decode(converter, &body)
+    }
+
+    /// Decodes the XML response body with the converter of `application/xml`,
+    /// the text is decoded with the charset of the `Content-Type` or the default encoding,
+    /// like `text_with_charset`.
+    pub fn xml<B: Backend, R: Response<B>, T: DeserializeOwned>(
+        res: R,
+        converters: &Converters,
+        default_encoding: &str,
+    ) -> Result<T> {
+        let converter = converters.decoder(convert::APPLICATION_XML)?;
+        let text = res.text_with_charset(default_encoding).map_err(B::error)?;
+
+        convert::decode(converter, text.as_bytes())
+    }
+
+    /// Decodes the Protocol Buffers response body as the message.
+    #[cfg(feature = "protobuf")]
+    pub fn protobuf<B: Backend, R: Response<B>, M: prost::Message + Default>(res: R) -> Result<M> {
+        convert::protobuf::negotiate(res.headers())?;
+
+        let body = res.bytes().map_err(B::error)?;
+
+        convert::protobuf::decode(&body)
+    }
+}
+
+/// A builder to construct and send an async request.
+pub trait AsyncRequestBuilder<B: Backend>: RequestBuilder<B> {
+    type Response: AsyncResponse<B>;
+
+    /// Streams the request body from an `Upload`.
+    fn upload(self, body: crate::upload::Upload) -> Self;
+
+    /// Constructs the `Request` and sends it the target URL, returning a future `Response`.
+    fn send(self) -> BoxFuture<'static, Result<Self::Response, B::Error>>;
+}
+
+/// A response to a submitted async `Request`.
+pub trait AsyncResponse<B: Backend>: Response<B> + Sized {
+    /// Try and deserialize the response body as JSON.
+    fn json<T: DeserializeOwned + Send + 'static>(self) -> BoxFuture<'static, Result<T, B::Error>>;
+
+    /// Get the response text.
+    fn text(self) -> BoxFuture<'static, Result<String, B::Error>>;
+
+    /// Get the response text given a specific encoding.
+    fn text_with_charset(self, default_encoding: &str) -> BoxFuture<'_, Result<String, B::Error>>;
+
+    /// Get the full response body as `Bytes`.
+    fn bytes(self) -> BoxFuture<'static, Result<Bytes, B::Error>>;
+
+    /// Stream the next chunk of the response body, returns `None` at the end of the body.
+    fn chunk(&mut self) -> BoxFuture<'_, Result<Option<Bytes>, B::Error>>;
+}
+
+/// An async client to execute the built requests with.
+pub trait AsyncClient<B: Backend> {
+    type Request: Request<B>;
+    type Response: AsyncResponse<B>;
+
+    /// Executes a `Request`.
+    fn execute(&self, req: Self::Request) -> BoxFuture<'static, Result<Self::Response, B::Error>>;
+}
+
+/// Executes the request with the credentials, interceptors and retry policy,
+/// and turns a non-success response into `Error::Status`.
+///
+/// A request rejected with `401 Unauthorized` is replayed once if the credentials were refreshed.
+pub async fn execute<B: Backend, C: AsyncClient<B>>(
+    client: &C,
+    req: Result<C::Request, B::Error>,
+    interceptors: &[Arc<dyn Interceptor>],
+    credentials: Option<&dyn CredentialProvider>,
+    retry: Option<&Retry>,
+) -> Result<C::Response> {
+    let mut req = req.map_err(B::error)?;
+    let send = |mut req: C::Request| async move {
+        if interceptors.is_empty() {
+            return client.execute(req).await.map_err(B::error);
+        }
+
+        let extensions = intercept_request(interceptors, &mut req)?;
+        let mut res = client.execute(req).await.map_err(B::error)?;
+
+        intercept_response(interceptors, &mut res, extensions)?;
+
+        Ok(res)
+    };
+    let send = |req: C::Request| async move {
+        match retry {
+            Some(retry) => {
+                retry
+                    .send::<B, _, _, _>(req, C::Request::try_clone, send)
+                    .await
+            }
+            None => send(req).await,
+        }
+    };
+    let res = match credentials {
+        Some(provider) => {
+            async_authorize(provider, &mut req).await?;
+
+            let replay = req.try_clone();
+            let res = send(req).await?;
+
+            match replay {
+                Some(mut req) if res.status() == StatusCode::UNAUTHORIZED => {
+                    if provider.async_refresh().await? {
+                        tracing::debug!("replay request with the refreshed credentials");
+
+                        async_authorize(provider, &mut req).await?;
+                        send(req).await?
+                    } else {
+                        res
+                    }
+                }
+                _ => res,
+            }
+        }
+        None => send(req).await?,
+    };
+
+    error_for_status(res).await
+}
+
+/// Turns a non-success response into `Error::Status` with the status code, headers and raw body.
+pub async fn error_for_status<B: Backend, R: AsyncResponse<B>>(res: R) -> Result<R> {
+    let status = res.status();
+
+    if status.is_client_error() || status.is_server_error() {
+        let headers = res.headers().clone();
+        let body = res.bytes().await.map_err(B::error)?;
+
+        Err(Error::Status(Box::new(StatusError {
+            status,
+            headers,
+            body,
+        })))
+    } else {
+        Ok(res)
+    }
+}
+
+/// Decodes the response body with the converter of its `Content-Type`.
+pub async fn decode<B: Backend, R: AsyncResponse<B>, T: DeserializeOwned>(
+    res: R,
+    converters: &Converters,
+) -> Result<T> {
+    let headers = res.headers().clone();
+    let body = res.bytes().await.map_err(B::error)?;
+
+    converters.decode(&headers, &body)
+}
+
+/// Decodes the response body with the converter of the media type,
+/// regardless of the response `Content-Type`.
+pub async fn decode_as<B: Backend, R: AsyncResponse<B>, T: DeserializeOwned>(
+    res: R,
+    converters: &Converters,
+    media_type: &str,
+) -> Result<T> {
+    let converter = converters.decoder(media_type)?;
+    let body = res.bytes().await.map_err(B::error)?;
+
+    convert::decode(converter, &body)
+}
+
+/// Decodes the XML response body with the converter of `application/xml`,
+/// the text is decoded with the charset of the `Content-Type` or the default encoding,
+/// like `text_with_charset`.
+pub async fn xml<B: Backend, R: AsyncResponse<B>, T: DeserializeOwned>(
+    res: R,
+    converters: &Converters,
+    default_encoding: &str,
+) -> Result<T> {
+    let converter = converters.decoder(convert::APPLICATION_XML)?;
+    let text = res
+        .text_with_charset(default_encoding)
+        .await
+        .map_err(B::error)?;
+
+    convert::decode(converter, text.as_bytes())
+}
+
+/// Decodes the Protocol Buffers response body as the message.
+#[cfg(feature = "protobuf")]
+pub async fn protobuf<B: Backend, R: AsyncResponse<B>, M: prost::Message + Default>(
+    res: R,
+) -> Result<M> {
+    convert::protobuf::negotiate(res.headers())?;
+
+    let body = res.bytes().await.map_err(B::error)?;
+
+    convert::protobuf::decode(&body)
+}
+
+pub mod prelude {
+    //! Brings the backend traits into scope for the generated code.
+    pub use super::blocking::{Client as _, RequestBuilder as _, Response as _};
+    pub use super::{
+        AsyncClient as _, AsyncRequestBuilder as _, AsyncResponse as _, Client as _,
+        ClientBuilder as _, Request as _, RequestBuilder as _, Response as _,
+    };
+}
@@ -0,0 +1,584 @@
+//! The converters of the request and response bodies, keyed by the media type.
+//!
+//! A `Converter` is registered on the service builder with `converter`,
+//! the request body of `#[body("media/type")]` is encoded with the converter of the media type,
+//! and the response body is decoded with the converter of its `Content-Type`.
+use std::fmt;
+use std::sync::Arc;
+
+use http::{header::CONTENT_TYPE, HeaderMap};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{Error, Result};
+
+pub use erased_serde;
+
+/// The media type of JSON.
+pub const APPLICATION_JSON: &str = "application/json";
+
+/// The media type of XML.
+pub const APPLICATION_XML: &str = "application/xml";
+
+/// The media type of MessagePack.
+pub const APPLICATION_MSGPACK: &str = "application/msgpack";
+
+/// The media type of CBOR.
+pub const APPLICATION_CBOR: &str = "application/cbor";
+
+/// The media type of Protocol Buffers.
+pub const APPLICATION_PROTOBUF: &str = "application/x-protobuf";
+
+/// Encodes the request bodies and decodes the response bodies of a media type.
+pub trait Converter: Send + Sync {
+    /// The media type of the encoded body, e.g. `application/json`.
+    fn media_type(&self) -> &str;
+
+    /// Returns true if the body of the media type could be converted.
+    ///
+    /// By default, the essence of the media type is compared with the one of the converter,
+    /// ignoring the parameters, e.g. `application/json; charset=utf-8`.
+    fn accepts(&self, media_type: &str) -> bool {
+        essence(media_type).eq_ignore_ascii_case(essence(self.media_type()))
+    }
+
+    /// Encodes the value as the request body.
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>>;
+
+    /// Decodes the response body with the deserializer passed to `visit`.
+    fn decode(
+        &self,
+        body: &[u8],
+        visit: &mut dyn FnMut(&mut dyn erased_serde::Deserializer<'_>) -> erased_serde::Result<()>,
+    ) -> Result<()>;
+
+    /// Decodes the response body of the media type, e.g. `text/xml; charset=iso-8859-1`.
+    ///
+    /// By default, the parameters of the media type are ignored and the body is decoded with `decode`.
+    fn decode_media_type(
+        &self,
+        media_type: &str,
+        body: &[u8],
+        visit: &mut dyn FnMut(&mut dyn erased_serde::Deserializer<'_>) -> erased_serde::Result<()>,
+    ) -> Result<()> {
+        let _ = media_type;
+
+        self.decode(body, visit)
+    }
+}
+
+/// Returns the media type without the parameters.
+pub fn essence(media_type: &str) -> &str {
+    media_type.split(';').next().unwrap_or_default().trim()
+}
+
+/// Returns the `charset` parameter of the media type, e.g. `utf-8` of `text/xml; charset="utf-8"`.
+pub fn charset(media_type: &str) -> Option<&str> {
+    media_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+
+        if name.trim().eq_ignore_ascii_case("charset") {
+            Some(value.trim().trim_matches('"'))
+        } else {
+            None
+        }
+    })
+}
+
+/// Encodes the value with the converter.
+pub fn encode<T: Serialize + ?Sized>(converter: &dyn Converter, value: &T) -> Result<Vec<u8>> {
+    converter.encode(&value)
+}
+
+/// Decodes the body with the converter.
+pub fn decode<T: DeserializeOwned>(converter: &dyn Converter, body: &[u8]) -> Result<T> {
+    deserialize(|visit| converter.decode(body, visit))
+}
+
+/// Decodes the body of the media type with the converter.
+pub fn decode_media_type<T: DeserializeOwned>(
+    converter: &dyn Converter,
+    media_type: &str,
+    body: &[u8],
+) -> Result<T> {
+    deserialize(|visit| converter.decode_media_type(media_type, body, visit))
+}
+
+/// Deserializes the value with the deserializer passed to the visitor by the converter.
+fn deserialize<T, F>(decode: F) -> Result<T>
+where
+    T: DeserializeOwned,
+    F: FnOnce(
+        &mut dyn FnMut(&mut dyn erased_serde::Deserializer<'_>) -> erased_serde::Result<()>,
+    ) -> Result<()>,
+{
+    let mut value = None;
+
+    decode(&mut |de| {
+        value = Some(erased_serde::deserialize(de)?);
+
+        Ok(())
+    })?;
+
+    value.ok_or_else(|| Error::Deserialize("the body was not decoded".into()))
+}
+
+/// The converter of `application/json`, with `serde_json`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Json;
+
+impl Converter for Json {
+    fn media_type(&self) -> &str {
+        APPLICATION_JSON
+    }
+
+    fn accepts(&self, media_type: &str) -> bool {
+        let media_type = essence(media_type);
+
+        media_type.eq_ignore_ascii_case(APPLICATION_JSON)
+            || media_type.to_ascii_lowercase().ends_with("+json")
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>> {
+        serde_json::to_vec(value).map_err(|err| Error::Serialize(err.into()))
+    }
+
+    fn decode(
+        &self,
+        body: &[u8],
+        visit: &mut dyn FnMut(&mut dyn erased_serde::Deserializer<'_>) -> erased_serde::Result<()>,
+    ) -> Result<()> {
+        let mut de = serde_json::Deserializer::from_slice(body);
+
+        visit(&mut <dyn erased_serde::Deserializer>::erase(&mut de))
+            .map_err(|err| Error::Deserialize(err.into()))?;
+
+        de.end().map_err(|err| Error::Deserialize(err.into()))
+    }
+}
+
+/// The converter of `application/xml`, with `quick-xml`.
+///
+/// The root element of the request body is named after the type of the value,
+/// the response body is decoded with the `charset` of its `Content-Type`, or as UTF-8.
+#[cfg(feature = "xml")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Xml;
+
+#[cfg(feature = "xml")]
+impl Converter for Xml {
+    fn media_type(&self) -> &str {
+        APPLICATION_XML
+    }
+
+    fn accepts(&self, media_type: &str) -> bool {
+        let media_type = essence(media_type).to_ascii_lowercase();
+
+        media_type == APPLICATION_XML || media_type == "text/xml" || media_type.ends_with("+xml")
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>> {
+        quick_xml::se::to_string(value)
+            .map(String::into_bytes)
+            .map_err(|err| Error::Serialize(err.into()))
+    }
+
+    fn decode(
+        &self,
+        body: &[u8],
+        visit: &mut dyn FnMut(&mut dyn erased_serde::Deserializer<'_>) -> erased_serde::Result<()>,
+    ) -> Result<()> {
+        let body = std::str::from_utf8(body).map_err(|err| Error::Deserialize(err.into()))?;
+        let mut de = quick_xml::de::Deserializer::from_str(body);
+
+        visit(&mut <dyn erased_serde::Deserializer>::erase(&mut de))
+            .map_err(|err| Error::Deserialize(err.into()))
+    }
+
+    fn decode_media_type(
+        &self,
+        media_type: &str,
+        body: &[u8],
+        visit: &mut dyn FnMut(&mut dyn erased_serde::Deserializer<'_>) -> erased_serde::Result<()>,
+    ) -> Result<()> {
+        match charset(media_type)
+            .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+        {
+            Some(encoding) => self.decode(encoding.decode(body).0.as_bytes(), visit),
+            None => self.decode(body, visit),
+        }
+    }
+}
+
+/// The converter of `application/msgpack`, with `rmp-serde`.
+///
+/// The structs are encoded as maps with the field names, like JSON.
+#[cfg(feature = "msgpack")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Msgpack;
+
+#[cfg(feature = "msgpack")]
+impl Converter for Msgpack {
+    fn media_type(&self) -> &str {
+        APPLICATION_MSGPACK
+    }
+
+    fn accepts(&self, media_type: &str) -> bool {
+        let media_type = essence(media_type).to_ascii_lowercase();
+
+        media_type == APPLICATION_MSGPACK
+            || media_type == "application/x-msgpack"
+            || media_type == "application/vnd.msgpack"
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>> {
+        rmp_serde::to_vec_named(&value).map_err(|err| Error::Serialize(err.into()))
+    }
+
+    fn decode(
+        &self,
+        body: &[u8],
+        visit: &mut dyn FnMut(&mut dyn erased_serde::Deserializer<'_>) -> erased_serde::Result<()>,
+    ) -> Result<()> {
+        let mut de = rmp_serde::Deserializer::from_read_ref(body);
+
+        visit(&mut <dyn erased_serde::Deserializer>::erase(&mut de))
+            .map_err(|err| Error::Deserialize(err.into()))
+    }
+}
+
+/// The converter of `application/cbor`, with `ciborium`.
+#[cfg(feature = "cbor")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl Converter for Cbor {
+    fn media_type(&self) -> &str {
+        APPLICATION_CBOR
+    }
+
+    fn accepts(&self, media_type: &str) -> bool {
+        let media_type = essence(media_type).to_ascii_lowercase();
+
+        media_type == APPLICATION_CBOR || media_type.ends_with("+cbor")
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>> {
+        let mut body = Vec::new();
+
+        ciborium::into_writer(&value, &mut body).map_err(|err| Error::Serialize(err.into()))?;
+
+        Ok(body)
+    }
+
+    fn decode(
+        &self,
+        mut body: &[u8],
+        visit: &mut dyn FnMut(&mut dyn erased_serde::Deserializer<'_>) -> erased_serde::Result<()>,
+    ) -> Result<()> {
+        let value: ciborium::Value =
+            ciborium::from_reader(&mut body).map_err(|err| Error::Deserialize(err.into()))?;
+
+        if !body.is_empty() {
+            return Err(Error::Deserialize(
+                "trailing data after the CBOR value".into(),
+            ));
+        }
+
+        visit(&mut <dyn erased_serde::Deserializer>::erase(
+            cbor::ValueDeserializer(&value),
+        ))
+        .map_err(|err| Error::Deserialize(err.into()))
+    }
+}
+
+/// `ciborium` only deserializes the owned types, so the body is decoded as a `Value` first,
+/// which is deserialized by the converter.
+#[cfg(feature = "cbor")]
+mod cbor {
+    use std::convert::TryFrom;
+
+    use ciborium::Value;
+    use serde::de::{
+        self,
+        value::{Error, MapAccessDeserializer, MapDeserializer, SeqDeserializer},
+        Error as _, IntoDeserializer, Visitor,
+    };
+
+    /// A `Deserializer` of a decoded CBOR `Value`.
+    pub struct ValueDeserializer<'a>(pub &'a Value);
+
+    impl<'de, 'a> IntoDeserializer<'de, Error> for ValueDeserializer<'a> {
+        type Deserializer = Self;
+
+        fn into_deserializer(self) -> Self {
+            self
+        }
+    }
+
+    impl<'de, 'a> de::Deserializer<'de> for ValueDeserializer<'a> {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.0 {
+                Value::Integer(n) => {
+                    let n = i128::from(*n);
+
+                    if let Ok(n) = u64::try_from(n) {
+                        visitor.visit_u64(n)
+                    } else if let Ok(n) = i64::try_from(n) {
+                        visitor.visit_i64(n)
+                    } else {
+                        visitor.visit_i128(n)
+                    }
+                }
+                Value::Bytes(bytes) => visitor.visit_bytes(bytes),
+                Value::Float(n) => visitor.visit_f64(*n),
+                Value::Text(text) => visitor.visit_str(text),
+                Value::Bool(b) => visitor.visit_bool(*b),
+                Value::Null => visitor.visit_unit(),
+                Value::Tag(_, value) => ValueDeserializer(value).deserialize_any(visitor),
+                Value::Array(items) => {
+                    let mut seq = SeqDeserializer::new(items.iter().map(ValueDeserializer));
+                    let value = visitor.visit_seq(&mut seq)?;
+
+                    seq.end()?;
+
+                    Ok(value)
+                }
+                Value::Map(entries) => {
+                    let mut map =
+                        MapDeserializer::new(entries.iter().map(|(key, value)| {
+                            (ValueDeserializer(key), ValueDeserializer(value))
+                        }));
+                    let value = visitor.visit_map(&mut map)?;
+
+                    map.end()?;
+
+                    Ok(value)
+                }
+                _ => Err(Error::custom("unsupported CBOR value")),
+            }
+        }
+
+        fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.0 {
+                Value::Null => visitor.visit_none(),
+                _ => visitor.visit_some(self),
+            }
+        }
+
+        fn deserialize_newtype_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_newtype_struct(self)
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            _variants: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            match self.0 {
+                Value::Text(variant) => visitor.visit_enum(variant.as_str().into_deserializer()),
+                Value::Map(entries) if entries.len() == 1 => {
+                    visitor.visit_enum(MapAccessDeserializer::new(MapDeserializer::new(
+                        entries
+                            .iter()
+                            .map(|(key, value)| (ValueDeserializer(key), ValueDeserializer(value))),
+                    )))
+                }
+                _ => Err(Error::custom(
+                    "expected a CBOR text or a map of one entry as enum",
+                )),
+            }
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+        }
+    }
+}
+
+/// The bodies of `prost::Message` types, which are not serialized with serde.
+#[cfg(feature = "protobuf")]
+pub mod protobuf {
+    use http::{header::CONTENT_TYPE, HeaderMap};
+
+    use super::{essence, APPLICATION_PROTOBUF};
+    use crate::{Error, Result};
+
+    pub use prost::Message;
+
+    /// Returns true if the media type is one of Protocol Buffers,
+    /// or `application/octet-stream` served by some of the servers.
+    pub fn accepts(media_type: &str) -> bool {
+        let media_type = essence(media_type).to_ascii_lowercase();
+
+        media_type == APPLICATION_PROTOBUF
+            || media_type == "application/protobuf"
+            || media_type == "application/vnd.google.protobuf"
+            || media_type == "application/octet-stream"
+    }
+
+    /// Ensures the response `Content-Type`, if any, is the one of Protocol Buffers.
+    pub fn negotiate(headers: &HeaderMap) -> Result<()> {
+        match headers.get(CONTENT_TYPE).map(|value| value.to_str()) {
+            None => Ok(()),
+            Some(Ok(media_type)) if accepts(media_type) => Ok(()),
+            Some(media_type) => Err(Error::Deserialize(
+                format!(
+                    "expected `{}` content, got `{}`",
+                    APPLICATION_PROTOBUF,
+                    media_type.unwrap_or_default()
+                )
+                .into(),
+            )),
+        }
+    }
+
+    /// Decodes the message from the response body.
+    pub fn decode<M: prost::Message + Default>(body: &[u8]) -> Result<M> {
+        M::decode(body).map_err(|err| Error::Deserialize(err.into()))
+    }
+}
+
+/// The converters of a service, the ones registered last take precedence.
+///
+/// The built-in `Json` converter, and `Xml`, `Msgpack` or `Cbor` with the feature of the same name,
+/// are always registered.
+/// `Json` decodes the response body without a `Content-Type` of the other converters.
+#[derive(Clone)]
+pub struct Converters(Vec<Arc<dyn Converter>>);
+
+impl Default for Converters {
+    fn default() -> Self {
+        Converters(vec![
+            #[cfg(feature = "xml")]
+            Arc::new(Xml),
+            #[cfg(feature = "msgpack")]
+            Arc::new(Msgpack),
+            #[cfg(feature = "cbor")]
+            Arc::new(Cbor),
+            Arc::new(Json),
+        ])
+    }
+}
+
+impl fmt::Debug for Converters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|converter| converter.media_type()))
+            .finish()
+    }
+}
+
+impl Converters {
+    /// Registers a converter, which takes precedence over the ones registered before.
+    pub fn register<C: Converter + 'static>(&mut self, converter: C) {
+        self.0.insert(0, Arc::new(converter));
+    }
+
+    /// Returns the converter of the media type.
+    pub fn get(&self, media_type: &str) -> Option<&dyn Converter> {
+        self.0
+            .iter()
+            .find(|converter| converter.accepts(media_type))
+            .map(|converter| converter.as_ref())
+    }
+
+    /// Encodes the request body with the converter of the media type.
+    pub fn encode<T: Serialize + ?Sized>(&self, media_type: &str, value: &T) -> Result<Vec<u8>> {
+        let converter = self
+            .get(media_type)
+            .ok_or_else(|| Error::Serialize(format!("no converter of `{}`", media_type).into()))?;
+
+        encode(converter, value)
+    }
+
+    /// Returns the converter of the media type to decode a response body,
+    /// e.g. `application/xml` which is registered by default with the `xml` feature.
+    pub fn decoder(&self, media_type: &str) -> Result<&dyn Converter> {
+        self.get(media_type)
+            .ok_or_else(|| Error::Deserialize(format!("no converter of `{}`", media_type).into()))
+    }
+
+    /// Returns the converter of the response `Content-Type`, or the built-in `Json` converter.
+    pub fn response(&self, headers: &HeaderMap) -> &dyn Converter {
+        headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|media_type| self.get(media_type))
+            .unwrap_or_else(|| self.0.last().expect("json").as_ref())
+    }
+
+    /// Decodes the response body with the converter of its `Content-Type`.
+    pub fn decode<T: DeserializeOwned>(&self, headers: &HeaderMap, body: &[u8]) -> Result<T> {
+        let media_type = headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or(APPLICATION_JSON);
+
+        decode_media_type(self.response(headers), media_type, body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn media_type_charset() {
+        assert_eq!(charset("text/xml; charset=iso-8859-1"), Some("iso-8859-1"));
+        assert_eq!(charset("text/xml;Charset=\"utf-8\""), Some("utf-8"));
+        assert_eq!(charset("text/xml; q=1"), None);
+        assert_eq!(charset("text/xml"), None);
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn decode_xml_charset() {
+        type Person = std::collections::HashMap<String, String>;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            "text/xml; charset=iso-8859-1".parse().unwrap(),
+        );
+        let body =
+            b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><person><name>Ren\xe9</name></person>";
+
+        let person: Person = Converters::default().decode(&headers, body).unwrap();
+        assert_eq!(person["name"], "Ren\u{e9}");
+
+        headers.insert(CONTENT_TYPE, "text/xml".parse().unwrap());
+        assert!(Converters::default()
+            .decode::<Person>(&headers, body)
+            .is_err());
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_round_trip() {
+        let value = serde_json::json!({"a": [1, -2, null, 1.5], "b": "x", "c": {"d": true}});
+        let body = encode(&Cbor, &value).unwrap();
+        assert_eq!(decode::<serde_json::Value>(&Cbor, &body).unwrap(), value);
+
+        let body = encode(&Cbor, &(Some(1u8), None::<u8>, b"ab".to_vec())).unwrap();
+        assert_eq!(
+            decode::<(Option<u8>, Option<u8>, Vec<u8>)>(&Cbor, &body).unwrap(),
+            (Some(1), None, b"ab".to_vec())
+        );
+
+        let body = encode(&Cbor, &Ok::<u32, String>(42)).unwrap();
+        assert_eq!(decode::<Result<u32, String>>(&Cbor, &body).unwrap(), Ok(42));
+
+        let mut body = encode(&Cbor, &1u8).unwrap();
+        body.push(0);
+        assert!(decode::<u8>(&Cbor, &body).is_err());
+    }
+}
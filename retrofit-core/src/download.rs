@@ -0,0 +1,204 @@
+//! The response bodies streamed on demand, without holding the full body in memory.
+use std::future::poll_fn;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::mem;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use http::{header::CONTENT_LENGTH, HeaderMap};
+
+use crate::{
+    backend::{AsyncResponse, Backend, BoxFuture},
+    Error, Result,
+};
+
+/// Returns the length of the body declared by the `Content-Length` header.
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+/// The state of the response, which is moved into the future of the pending chunk.
+enum State<R, E> {
+    Idle(R),
+    Pending(BoxFuture<'static, (R, Result<Option<Bytes>, E>)>),
+    Done,
+}
+
+/// The body of a response, declared with `-> Download`.
+///
+/// The async `Download` is a `Stream` of the chunks of the body.
+pub struct Download<B, R>
+where
+    B: Backend,
+{
+    headers: HeaderMap,
+    state: State<R, B::Error>,
+    backend: PhantomData<fn() -> B>,
+}
+
+impl<B, R> Download<B, R>
+where
+    B: Backend,
+    R: AsyncResponse<B>,
+{
+    pub fn new(res: R) -> Self {
+        Download {
+            headers: res.headers().clone(),
+            state: State::Idle(res),
+            backend: PhantomData,
+        }
+    }
+
+    /// Get the `Headers` of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Get the length of the body declared by the `Content-Length` header, if any.
+    pub fn content_length(&self) -> Option<u64> {
+        content_length(&self.headers)
+    }
+}
+
+impl<B, R> Download<B, R>
+where
+    B: Backend + 'static,
+    R: AsyncResponse<B> + Send + 'static,
+{
+    /// Writes the chunks of the body into the writer as they are received,
+    /// returns the number of bytes written.
+    ///
+    /// A failed write is returned as `Error::Io`.
+    pub async fn copy_to<W: Write + ?Sized>(mut self, writer: &mut W) -> Result<u64> {
+        let mut written = 0;
+
+        while let Some(chunk) = poll_fn(|cx| Pin::new(&mut self).poll_next(cx)).await {
+            let chunk = chunk?;
+
+            writer.write_all(&chunk).map_err(Error::Io)?;
+            written += chunk.len() as u64;
+        }
+
+        Ok(written)
+    }
+}
+
+impl<B, R> Unpin for Download<B, R> where B: Backend {}
+
+impl<B, R> Stream for Download<B, R>
+where
+    B: Backend + 'static,
+    R: AsyncResponse<B> + Send + 'static,
+{
+    type Item = Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            match mem::replace(&mut this.state, State::Done) {
+                State::Idle(mut res) => {
+                    this.state = State::Pending(Box::pin(async move {
+                        let chunk = res.chunk().await;
+
+                        (res, chunk)
+                    }));
+                }
+                State::Pending(mut pending) => {
+                    return match pending.as_mut().poll(cx) {
+                        Poll::Ready((res, Ok(Some(chunk)))) => {
+                            this.state = State::Idle(res);
+
+                            Poll::Ready(Some(Ok(chunk)))
+                        }
+                        Poll::Ready((_, Ok(None))) => Poll::Ready(None),
+                        Poll::Ready((_, Err(err))) => Poll::Ready(Some(Err(B::error(err)))),
+                        Poll::Pending => {
+                            this.state = State::Pending(pending);
+
+                            Poll::Pending
+                        }
+                    }
+                }
+                State::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+pub mod blocking {
+    //! The blocking downloads.
+    use std::io::{self, Read};
+
+    use super::*;
+    use crate::backend::Response;
+
+    /// The body of a response, declared with `-> Download`.
+    ///
+    /// The blocking `Download` reads the body on demand as a `Read`.
+    pub struct Download<B, R> {
+        res: R,
+        backend: PhantomData<fn() -> B>,
+    }
+
+    impl<B, R> Download<B, R>
+    where
+        B: Backend,
+        R: Response<B>,
+    {
+        pub fn new(res: R) -> Self {
+            Download {
+                res,
+                backend: PhantomData,
+            }
+        }
+
+        /// Get the `Headers` of the response.
+        pub fn headers(&self) -> &HeaderMap {
+            self.res.headers()
+        }
+
+        /// Get the length of the body declared by the `Content-Length` header, if any.
+        pub fn content_length(&self) -> Option<u64> {
+            content_length(self.res.headers())
+        }
+
+        /// Copies the body into the writer, returns the number of bytes written.
+        ///
+        /// A failed read is returned as `Error::Transport`, and a failed write as `Error::Io`.
+        pub fn copy_to<W: Write + ?Sized>(&mut self, writer: &mut W) -> Result<u64>
+        where
+            R: Read,
+        {
+            let mut buf = [0; 8 * 1024];
+            let mut written = 0;
+
+            loop {
+                let len = match self.res.read(&mut buf) {
+                    Ok(0) => return Ok(written),
+                    Ok(len) => len,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(Error::Transport(err.into())),
+                };
+
+                writer.write_all(&buf[..len]).map_err(Error::Io)?;
+                written += len as u64;
+            }
+        }
+    }
+
+    impl<B, R> Read for Download<B, R>
+    where
+        R: Read,
+    {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.res.read(buf)
+        }
+    }
+}
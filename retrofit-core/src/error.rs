@@ -0,0 +1,226 @@
+use std::convert::Infallible;
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
+use bytes::Bytes;
+use http::{HeaderMap, StatusCode};
+use serde::de::DeserializeOwned;
+
+use crate::convert::Converters;
+
+/// A boxed error from the backend, serializer or deserializer.
+pub type BoxError = Box<dyn StdError + Send + Sync>;
+
+/// A `Result` alias where the `Err` case is `retrofit::Error`.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The errors that may occur when sending a request or decoding the response.
+///
+/// `E` is the typed error body of the API, declared with `#[response(error = E)]` or `#[service(error = E)]`.
+#[derive(Debug)]
+pub enum Error<E = Infallible> {
+    /// Failed to build the request, e.g. an invalid URL or header.
+    Request(BoxError),
+    /// Failed to serialize the request query or body.
+    Serialize(BoxError),
+    /// Failed to send the request or receive the response, e.g. connect, timeout or redirect error.
+    Transport(BoxError),
+    /// The server returned a non-success status code.
+    Status(Box<StatusError>),
+    /// The server returned a non-success status code with the typed error body.
+    Api(Box<ApiError<E>>),
+    /// Failed to deserialize the response body or headers.
+    Deserialize(BoxError),
+    /// Failed to write the response body into the local writer, e.g. a file.
+    Io(io::Error),
+}
+
+impl Error {
+    /// Decode the body of a non-success response as the typed error body `E`,
+    /// with the converter of its `Content-Type`.
+    ///
+    /// The `Error::Status` is preserved if the body could not be decoded as `E`.
+    pub fn api_error<E: DeserializeOwned>(self, converters: &Converters) -> Error<E> {
+        match self {
+            Error::Request(err) => Error::Request(err),
+            Error::Serialize(err) => Error::Serialize(err),
+            Error::Transport(err) => Error::Transport(err),
+            Error::Status(err) => match converters.decode(&err.headers, &err.body) {
+                Ok(error) => Error::Api(Box::new(ApiError {
+                    status: err.status,
+                    headers: err.headers,
+                    error,
+                })),
+                Err(_) => Error::Status(err),
+            },
+            Error::Api(never) => match never.error {},
+            Error::Deserialize(err) => Error::Deserialize(err),
+            Error::Io(err) => Error::Io(err),
+        }
+    }
+}
+
+impl<E> Error<E> {
+    /// Returns the status code if the server returned a non-success status code.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::Status(err) => Some(err.status),
+            Error::Api(err) => Some(err.status),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the error is from building the request.
+    pub fn is_request(&self) -> bool {
+        matches!(self, Error::Request(_))
+    }
+
+    /// Returns true if the error is from serializing the request.
+    pub fn is_serialize(&self) -> bool {
+        matches!(self, Error::Serialize(_))
+    }
+
+    /// Returns true if the error is from sending the request or receiving the response.
+    pub fn is_transport(&self) -> bool {
+        matches!(self, Error::Transport(_))
+    }
+
+    /// Returns true if the server returned a non-success status code.
+    pub fn is_status(&self) -> bool {
+        matches!(self, Error::Status(_))
+    }
+
+    /// Returns true if the server returned the typed error body.
+    pub fn is_api(&self) -> bool {
+        matches!(self, Error::Api(_))
+    }
+
+    /// Returns the typed error body if the server returned it.
+    pub fn api(&self) -> Option<&E> {
+        match self {
+            Error::Api(err) => Some(&err.error),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the error is from deserializing the response body or headers.
+    pub fn is_deserialize(&self) -> bool {
+        matches!(self, Error::Deserialize(_))
+    }
+
+    /// Returns true if the error is from writing the response body into the local writer.
+    pub fn is_io(&self) -> bool {
+        matches!(self, Error::Io(_))
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Request(err) => write!(f, "builder error: {}", err),
+            Error::Serialize(err) => write!(f, "error serializing request: {}", err),
+            Error::Transport(err) => write!(f, "error sending request: {}", err),
+            Error::Status(err) => err.fmt(f),
+            Error::Api(err) => write!(f, "API error ({}): {:?}", err.status, err.error),
+            Error::Deserialize(err) => write!(f, "error decoding response: {}", err),
+            Error::Io(err) => write!(f, "error writing response: {}", err),
+        }
+    }
+}
+
+impl<E: fmt::Debug> StdError for Error<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Request(err)
+            | Error::Serialize(err)
+            | Error::Transport(err)
+            | Error::Deserialize(err) => Some(err.as_ref()),
+            Error::Status(err) => Some(err.as_ref()),
+            Error::Api(_) => None,
+            Error::Io(err) => Some(err),
+        }
+    }
+}
+
+/// The non-success response returned by the server.
+#[derive(Clone, Debug)]
+pub struct StatusError {
+    /// The status code of the response.
+    pub status: StatusCode,
+    /// The headers of the response.
+    pub headers: HeaderMap,
+    /// The raw body of the response.
+    pub body: Bytes,
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let prefix = if self.status.is_client_error() {
+            "HTTP status client error"
+        } else {
+            "HTTP status server error"
+        };
+
+        write!(f, "{} ({})", prefix, self.status)
+    }
+}
+
+impl StdError for StatusError {}
+
+/// The non-success response returned by the server with the typed error body.
+#[derive(Clone, Debug)]
+pub struct ApiError<E> {
+    /// The status code of the response.
+    pub status: StatusCode,
+    /// The headers of the response.
+    pub headers: HeaderMap,
+    /// The typed error body of the response.
+    pub error: E,
+}
+
+#[cfg(test)]
+mod tests {
+    use http::header::CONTENT_TYPE;
+
+    use super::*;
+
+    fn status_error(content_type: &str, body: &'static [u8]) -> Error {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, content_type.parse().unwrap());
+
+        Error::Status(Box::new(StatusError {
+            status: StatusCode::NOT_FOUND,
+            headers,
+            body: Bytes::from_static(body),
+        }))
+    }
+
+    #[test]
+    fn decode_api_error() {
+        type Body = std::collections::HashMap<String, String>;
+
+        let err = status_error("application/json", br#"{"message":"Not Found"}"#)
+            .api_error::<Body>(&Converters::default());
+        assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+        assert_eq!(err.api().unwrap()["message"], "Not Found");
+
+        let err = status_error("text/html", b"<h1>Not Found</h1>")
+            .api_error::<Body>(&Converters::default());
+        assert!(err.is_status());
+        assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn decode_xml_api_error() {
+        type Body = std::collections::HashMap<String, String>;
+
+        let err = status_error(
+            "application/xml",
+            b"<error><message>Not Found</message></error>",
+        )
+        .api_error::<Body>(&Converters::default());
+        assert_eq!(err.api().unwrap()["message"], "Not Found");
+    }
+}
@@ -0,0 +1,37 @@
+//! Parsing of the response headers returned with `#[response(header = "...")]`.
+use std::fmt::Display;
+use std::str::FromStr;
+
+use http::HeaderMap;
+
+use crate::{Error, Result};
+
+/// Parse the value of the response header, e.g. `x-ratelimit-remaining` as `u32`.
+pub fn parse<T>(headers: &HeaderMap, name: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    parse_optional(headers, name)?
+        .ok_or_else(|| Error::Deserialize(format!("missing `{}` header", name).into()))
+}
+
+/// Parse the value of the response header if it is present, declared as `Option<T>`.
+pub fn parse_optional<T>(headers: &HeaderMap, name: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    headers
+        .get(name)
+        .map(|value| {
+            value
+                .to_str()
+                .map_err(|err| err.to_string())
+                .and_then(|value| value.trim().parse().map_err(|err: T::Err| err.to_string()))
+                .map_err(|err| {
+                    Error::Deserialize(format!("invalid `{}` header: {}", name, err).into())
+                })
+        })
+        .transpose()
+}
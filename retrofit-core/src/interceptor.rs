@@ -0,0 +1,97 @@
+//! The interceptors of the outgoing requests and incoming responses.
+use std::mem;
+use std::sync::Arc;
+
+use http::{Extensions, Uri};
+
+use crate::{
+    backend::{Backend, Request, Response},
+    Error, Result,
+};
+
+/// Inspects and mutates the requests and responses of every method, e.g. logging, auth or metrics.
+///
+/// The interceptors are registered on the service builder, the requests are intercepted
+/// in the order they are registered, and the responses are intercepted in the reverse order.
+///
+/// The request extensions are moved to the response,
+/// so an interceptor could pass the state from the request to the response, e.g. the start time.
+pub trait Interceptor: Send + Sync {
+    /// Inspects or mutates the method, URI and headers of the outgoing request.
+    fn request(&self, req: &mut http::Request<()>) -> Result<()> {
+        let _ = req;
+
+        Ok(())
+    }
+
+    /// Inspects the status, or mutates the headers of the incoming response.
+    fn response(&self, res: &mut http::Response<()>) -> Result<()> {
+        let _ = res;
+
+        Ok(())
+    }
+}
+
+impl<F> Interceptor for F
+where
+    F: Fn(&mut http::Request<()>) -> Result<()> + Send + Sync,
+{
+    fn request(&self, req: &mut http::Request<()>) -> Result<()> {
+        self(req)
+    }
+}
+
+/// Intercepts the outgoing request, returns the extensions of the request.
+pub(crate) fn intercept_request<B: Backend, R: Request<B>>(
+    interceptors: &[Arc<dyn Interceptor>],
+    req: &mut R,
+) -> Result<Extensions> {
+    let mut view = http::Request::new(());
+
+    *view.method_mut() = req.method().clone();
+    *view.uri_mut() = req
+        .url()
+        .parse::<Uri>()
+        .map_err(|err| Error::Request(err.into()))?;
+    *view.headers_mut() = mem::take(req.headers_mut());
+
+    let res = interceptors
+        .iter()
+        .try_for_each(|interceptor| interceptor.request(&mut view));
+    let (parts, _) = view.into_parts();
+
+    *req.method_mut() = parts.method;
+    *req.headers_mut() = parts.headers;
+
+    res?;
+
+    let uri = parts.uri.to_string();
+
+    if uri != req.url() {
+        req.set_url(&uri)?;
+    }
+
+    Ok(parts.extensions)
+}
+
+/// Intercepts the incoming response with the extensions of the request.
+pub(crate) fn intercept_response<B: Backend, R: Response<B>>(
+    interceptors: &[Arc<dyn Interceptor>],
+    res: &mut R,
+    extensions: Extensions,
+) -> Result<()> {
+    let mut view = http::Response::new(());
+
+    *view.status_mut() = res.status();
+    *view.headers_mut() = mem::take(res.headers_mut());
+    *view.extensions_mut() = extensions;
+
+    let intercepted = interceptors
+        .iter()
+        .rev()
+        .try_for_each(|interceptor| interceptor.response(&mut view));
+
+    *res.headers_mut() = mem::take(view.headers_mut());
+
+    intercepted
+}
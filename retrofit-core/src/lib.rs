@@ -1,19 +1,45 @@
-use std::error::Error;
+use std::error::Error as StdError;
 use std::future::Future;
 
+mod auth;
+pub mod backend;
+pub mod convert;
+pub mod download;
+mod error;
+pub mod header;
+mod interceptor;
+pub mod ndjson;
+pub mod paginate;
+pub mod path;
+pub mod query;
+mod response;
+mod retry;
+pub mod upload;
+
+pub use http;
+
+pub use self::auth::{CredentialProvider, Credentials};
+pub use self::convert::{Converter, Converters};
+pub use self::error::{ApiError, BoxError, Error, Result, StatusError};
+pub use self::interceptor::Interceptor;
+pub use self::paginate::Pagination;
+pub use self::response::Response;
+pub use self::retry::{Backoff, Retry};
+
 pub trait Call<T>: AsyncCall<T> {
     fn send(self) -> Result<T, Self::Error>;
 }
 
 pub trait AsyncCall<T> {
-    type Error: Error + Send + Sync;
+    type Error: StdError + Send + Sync;
     type Future: Future<Output = Result<T, Self::Error>>;
 
     fn async_send(self) -> Self::Future;
 }
 
 pub trait Service {
-    type Error: Error + Send + Sync;
+    type Error: StdError + Send + Sync;
     type Body;
     type Form;
+    type Upload;
 }
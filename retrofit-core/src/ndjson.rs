@@ -0,0 +1,333 @@
+//! The newline-delimited JSON bodies, decoded line by line as the body is received.
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use http::HeaderMap;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    backend::{AsyncResponse, Backend},
+    download::Download,
+    Error, Result,
+};
+
+/// Decodes a line of the body, the blank lines are skipped.
+fn decode<T: DeserializeOwned>(line: &[u8]) -> Option<Result<T>> {
+    if line.iter().all(u8::is_ascii_whitespace) {
+        None
+    } else {
+        Some(serde_json::from_slice(line).map_err(|err| Error::Deserialize(err.into())))
+    }
+}
+
+/// Splits the chunks of a body into lines, a line may span several chunks.
+#[derive(Debug, Default)]
+struct Lines {
+    buf: Vec<u8>,
+    /// The start of the next line in `buf`, the lines before it have been decoded.
+    start: usize,
+    /// The end of the scanned bytes in `buf`, there is no `\n` between `start` and it.
+    scanned: usize,
+}
+
+impl Lines {
+    /// Appends a chunk of the body, the decoded lines are discarded at once.
+    fn extend(&mut self, chunk: &[u8]) {
+        self.buf.drain(..self.start);
+        self.scanned -= self.start;
+        self.start = 0;
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Discards the incomplete line.
+    fn clear(&mut self) {
+        self.buf.clear();
+        self.start = 0;
+        self.scanned = 0;
+    }
+
+    /// Decodes the next complete line, or the last line without `\n` once the body is `done`.
+    fn decode<T: DeserializeOwned>(&mut self, done: bool) -> Option<Result<T>> {
+        while let Some(pos) = self.buf[self.scanned..].iter().position(|&b| b == b'\n') {
+            let start = self.start;
+            let end = self.scanned + pos + 1;
+
+            self.start = end;
+            self.scanned = end;
+
+            if let Some(item) = decode(&self.buf[start..end]) {
+                return Some(item);
+            }
+        }
+
+        self.scanned = self.buf.len();
+
+        if done {
+            let item = decode(&self.buf[self.start..]);
+
+            self.clear();
+
+            item
+        } else {
+            None
+        }
+    }
+}
+
+/// The items of a newline-delimited JSON body, declared with `#[response(ndjson())]` and `-> Ndjson<T>`.
+///
+/// The async `Ndjson` is a `Stream` of the decoded lines.
+pub struct Ndjson<B, R, T>
+where
+    B: Backend,
+{
+    body: Download<B, R>,
+    lines: Lines,
+    done: bool,
+    item: PhantomData<fn() -> T>,
+}
+
+impl<B, R, T> Ndjson<B, R, T>
+where
+    B: Backend,
+    R: AsyncResponse<B>,
+{
+    pub fn new(res: R) -> Self {
+        Ndjson {
+            body: Download::new(res),
+            lines: Lines::default(),
+            done: false,
+            item: PhantomData,
+        }
+    }
+
+    /// Get the `Headers` of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        self.body.headers()
+    }
+}
+
+impl<B, R, T> Stream for Ndjson<B, R, T>
+where
+    B: Backend + 'static,
+    R: AsyncResponse<B> + Send + 'static,
+    T: DeserializeOwned,
+{
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(item) = this.lines.decode(this.done) {
+                return Poll::Ready(Some(item));
+            }
+
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            match Pin::new(&mut this.body).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => this.lines.extend(&chunk),
+                Poll::Ready(Some(Err(err))) => {
+                    this.done = true;
+                    this.lines.clear();
+
+                    return Poll::Ready(Some(Err(err)));
+                }
+                Poll::Ready(None) => this.done = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+pub mod blocking {
+    //! The blocking newline-delimited JSON bodies.
+    use std::io::{self, BufRead, BufReader, Read};
+
+    use super::*;
+    use crate::{backend::Response, download::blocking::Download};
+
+    /// The items of a newline-delimited JSON body, declared with `#[response(ndjson())]` and `-> Ndjson<T>`.
+    ///
+    /// The blocking `Ndjson` is an `Iterator` of the decoded lines,
+    /// a failed read is returned as `Error::Transport`.
+    pub struct Ndjson<B, R, T> {
+        reader: BufReader<Download<B, R>>,
+        lines: Lines,
+        done: bool,
+        item: PhantomData<fn() -> T>,
+    }
+
+    impl<B, R, T> Ndjson<B, R, T>
+    where
+        B: Backend,
+        R: Response<B> + Read,
+    {
+        pub fn new(res: R) -> Self {
+            Ndjson {
+                reader: BufReader::new(Download::new(res)),
+                lines: Lines::default(),
+                done: false,
+                item: PhantomData,
+            }
+        }
+
+        /// Get the `Headers` of the response.
+        pub fn headers(&self) -> &HeaderMap {
+            self.reader.get_ref().headers()
+        }
+    }
+
+    impl<B, R, T> Iterator for Ndjson<B, R, T>
+    where
+        R: Read,
+        T: DeserializeOwned,
+    {
+        type Item = Result<T>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            next(&mut self.reader, &mut self.lines, &mut self.done)
+        }
+    }
+
+    /// Reads the chunks of the body until the next line is decoded.
+    pub(super) fn next<R: BufRead, T: DeserializeOwned>(
+        reader: &mut R,
+        lines: &mut Lines,
+        done: &mut bool,
+    ) -> Option<Result<T>> {
+        loop {
+            if let Some(item) = lines.decode(*done) {
+                return Some(item);
+            }
+
+            if *done {
+                return None;
+            }
+
+            match reader.fill_buf() {
+                Ok([]) => *done = true,
+                Ok(chunk) => {
+                    let len = chunk.len();
+
+                    lines.extend(chunk);
+                    reader.consume(len);
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    *done = true;
+                    lines.clear();
+
+                    return Some(Err(Error::Transport(err.into())));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    const BODY: &[u8] = b"{\"id\":0}\n\n{\"id\":1}\r\n  \r\n{\"id\":2}";
+
+    /// Decodes the body received in chunks of the size, as the async `Ndjson`.
+    fn split(body: &[u8], size: usize) -> Vec<Value> {
+        let mut lines = Lines::default();
+        let mut items = vec![];
+
+        for chunk in body.chunks(size) {
+            lines.extend(chunk);
+
+            while let Some(item) = lines.decode(false) {
+                items.push(item.unwrap());
+            }
+        }
+
+        while let Some(item) = lines.decode(true) {
+            items.push(item.unwrap());
+        }
+
+        items
+    }
+
+    /// Decodes the body read in chunks of the size, as the blocking `Ndjson`.
+    fn read(body: &[u8], size: usize) -> Vec<Value> {
+        let mut reader = BufReader::with_capacity(size, body);
+        let mut lines = Lines::default();
+        let mut done = false;
+
+        std::iter::from_fn(|| blocking::next(&mut reader, &mut lines, &mut done))
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn split_lines_across_chunks() {
+        let items = vec![json!({"id": 0}), json!({"id": 1}), json!({"id": 2})];
+
+        for size in 1..=BODY.len() {
+            assert_eq!(split(BODY, size), items, "chunks of {} bytes", size);
+            assert_eq!(read(BODY, size), items, "chunks of {} bytes", size);
+        }
+    }
+
+    #[test]
+    fn last_line() {
+        assert_eq!(split(b"{\"id\":0}\n", 4), vec![json!({"id": 0})]);
+        assert_eq!(read(b"{\"id\":0}\n", 4), vec![json!({"id": 0})]);
+        assert_eq!(split(b"{\"id\":0}", 4), vec![json!({"id": 0})]);
+        assert_eq!(read(b"{\"id\":0}", 4), vec![json!({"id": 0})]);
+        assert!(split(b"\r\n\n ", 2).is_empty());
+        assert!(read(b"\r\n\n ", 2).is_empty());
+    }
+
+    #[test]
+    fn drain_decoded_lines() {
+        let mut lines = Lines::default();
+
+        lines.extend(b"{\"id\":0}\n{\"id\":1}\n{\"id\"");
+        assert!(lines.decode::<Value>(false).is_some());
+        assert!(lines.decode::<Value>(false).is_some());
+        assert!(lines.decode::<Value>(false).is_none());
+        assert_eq!((lines.start, lines.scanned, lines.buf.len()), (18, 23, 23));
+
+        lines.extend(b":2}\n");
+        assert_eq!((lines.start, lines.scanned), (0, 5));
+        assert_eq!(lines.buf, b"{\"id\":2}\n");
+        assert_eq!(
+            lines.decode::<Value>(false).unwrap().unwrap(),
+            json!({"id": 2})
+        );
+    }
+
+    #[test]
+    fn invalid_line() {
+        let mut lines = Lines::default();
+
+        lines.extend(b"{\"id\":0}\nnot json\n{\"id\":1}");
+
+        assert_eq!(
+            lines.decode::<Value>(false).unwrap().unwrap(),
+            json!({"id": 0})
+        );
+        assert!(matches!(
+            lines.decode::<Value>(false),
+            Some(Err(Error::Deserialize(_)))
+        ));
+        assert!(lines.decode::<Value>(false).is_none());
+        assert_eq!(
+            lines.decode::<Value>(true).unwrap().unwrap(),
+            json!({"id": 1})
+        );
+        assert!(lines.decode::<Value>(true).is_none());
+    }
+}
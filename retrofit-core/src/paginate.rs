@@ -0,0 +1,863 @@
+//! The pagination of the list methods.
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use http::{header::LINK, HeaderMap};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use url::Url;
+
+use crate::{
+    backend::{self, AsyncClient, AsyncResponse, Backend, BoxFuture, Request, Response},
+    Converters, CredentialProvider, Error, Interceptor, Result, Retry,
+};
+
+/// How the next page is requested, declared with `#[paginate(...)]`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pagination {
+    /// Follows the `rel="next"` link of the `Link` header, every page is a JSON array.
+    ///
+    /// The pages stop at a link to the current page, a link to another origin is returned as `Error::Request`.
+    LinkHeader,
+    /// Sets the `query` parameter to the cursor at the `next` JSON pointer of the page,
+    /// the items are at the `items` JSON pointer.
+    Cursor {
+        query: &'static str,
+        next: &'static str,
+        items: &'static str,
+    },
+    /// Sets the `offset` and `limit` query parameters, every page is a JSON array,
+    /// the last page has less than `size` items.
+    Offset {
+        offset: &'static str,
+        limit: &'static str,
+        size: usize,
+    },
+}
+
+/// The items of a page, and the URL of the next page if any.
+type Page<T> = (Vec<T>, Option<String>);
+
+impl Pagination {
+    /// Returns the URL of the first page.
+    pub fn first(&self, url: &str) -> Result<String> {
+        match *self {
+            Pagination::Offset {
+                offset,
+                limit,
+                size,
+            } => set_query(url, &[(offset, "0".into()), (limit, size.to_string())]),
+            _ => Ok(url.to_string()),
+        }
+    }
+
+    /// Decodes the items of the page, and returns the URL of the next page if any.
+    pub fn page<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<Page<T>> {
+        match *self {
+            Pagination::LinkHeader => {
+                let items = decode(body)?;
+                let current = Url::parse(url).map_err(|err| Error::Request(err.into()))?;
+                let next = next_link(headers)
+                    .map(|link| {
+                        current
+                            .join(&link)
+                            .map_err(|err| Error::Deserialize(err.into()))
+                    })
+                    .transpose()?
+                    .filter(|next| {
+                        let repeated = *next == current;
+
+                        if repeated {
+                            tracing::debug!(%next, "stop at the link to the current page");
+                        }
+
+                        !repeated
+                    })
+                    .map(String::from);
+
+                Ok((items, next))
+            }
+            Pagination::Cursor { query, next, items } => {
+                let mut page: Value = decode(body)?;
+                let items = match page.pointer_mut(items) {
+                    Some(items) => serde_json::from_value(items.take())
+                        .map_err(|err| Error::Deserialize(err.into()))?,
+                    None => vec![],
+                };
+                let cursor = match page.pointer(next) {
+                    Some(Value::String(cursor)) if !cursor.is_empty() => Some(cursor.clone()),
+                    Some(Value::Number(cursor)) => Some(cursor.to_string()),
+                    _ => None,
+                };
+                let current = query_param(url, query);
+                let next = cursor
+                    .filter(|cursor| {
+                        let repeated = current.as_ref() == Some(cursor);
+
+                        if repeated {
+                            tracing::debug!(%cursor, "stop at the repeated cursor");
+                        }
+
+                        !repeated
+                    })
+                    .map(|cursor| set_query(url, &[(query, cursor)]))
+                    .transpose()?;
+
+                Ok((items, next))
+            }
+            Pagination::Offset { offset, size, .. } => {
+                let items: Vec<T> = decode(body)?;
+                let next = if items.is_empty() || items.len() < size {
+                    None
+                } else {
+                    let current = query_param(url, offset)
+                        .and_then(|value| value.parse::<usize>().ok())
+                        .unwrap_or_default();
+
+                    Some(set_query(
+                        url,
+                        &[(offset, (current + items.len()).to_string())],
+                    )?)
+                };
+
+                Ok((items, next))
+            }
+        }
+    }
+}
+
+fn decode<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    serde_json::from_slice(body).map_err(|err| Error::Deserialize(err.into()))
+}
+
+/// Returns the value of the query parameter of the URL.
+fn query_param(url: &str, name: &str) -> Option<String> {
+    Url::parse(url).ok().and_then(|url| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    })
+}
+
+/// Returns true if the URLs have the same scheme, host and port.
+fn same_origin(url: &str, other: &str) -> bool {
+    match (Url::parse(url), Url::parse(other)) {
+        (Ok(url), Ok(other)) => url.origin() == other.origin(),
+        _ => false,
+    }
+}
+
+/// Replaces the query parameters of the URL.
+fn set_query(url: &str, params: &[(&str, String)]) -> Result<String> {
+    let mut url = Url::parse(url).map_err(|err| Error::Request(err.into()))?;
+    let pairs = url
+        .query_pairs()
+        .filter(|(key, _)| !params.iter().any(|(name, _)| key == name))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .extend_pairs(params.iter().map(|(name, value)| (name, value)));
+
+    Ok(url.into())
+}
+
+/// Returns the `rel="next"` link of the `Link` header, e.g. `<https://api.github.com/user/repos?page=2>; rel="next"`.
+fn next_link(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(links)
+        .find_map(|(target, rel)| {
+            rel.split_whitespace()
+                .any(|rel| rel.eq_ignore_ascii_case("next"))
+                .then(|| target.to_string())
+        })
+}
+
+/// Parses the links of a `Link` header value, and returns the target and `rel` parameter of every link.
+///
+/// The target is delimited by `<` and `>`, so it may contain commas and semicolons,
+/// and a quoted parameter value may contain them as well.
+fn links(value: &str) -> Vec<(&str, String)> {
+    let mut links = vec![];
+    let mut rest = value;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+
+        let (target, tail) = match rest.strip_prefix('<').and_then(|s| s.split_once('>')) {
+            Some(link) => link,
+            None => return links,
+        };
+        let mut rel = String::new();
+
+        rest = tail;
+
+        while let Some(tail) = rest.trim_start().strip_prefix(';') {
+            let (name, value, tail) = link_param(tail);
+
+            if name.eq_ignore_ascii_case("rel") {
+                rel = value;
+            }
+
+            rest = tail;
+        }
+
+        links.push((target, rel));
+
+        let tail = rest.trim_start();
+
+        rest = match tail.find(',') {
+            Some(0) => tail,
+            Some(pos) => {
+                tracing::debug!(link = %tail, "skip the malformed link");
+
+                &tail[pos..]
+            }
+            None => return links,
+        };
+    }
+}
+
+/// Parses a `name=value` or `name="quoted value"` parameter of a link, returns the rest after it.
+fn link_param(param: &str) -> (&str, String, &str) {
+    let param = param.trim_start();
+    let end = param.find(['=', ';', ',']).unwrap_or(param.len());
+    let name = param[..end].trim();
+    let rest = match param[end..].strip_prefix('=') {
+        Some(rest) => rest.trim_start(),
+        None => return (name, String::new(), &param[end..]),
+    };
+
+    if let Some(quoted) = rest.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '"' => return (name, value, &quoted[pos + 1..]),
+                '\\' => value.extend(chars.next().map(|(_, c)| c)),
+                c => value.push(c),
+            }
+        }
+
+        (name, value, "")
+    } else {
+        let end = rest.find([';', ',']).unwrap_or(rest.len());
+
+        (name, rest[..end].trim().to_string(), &rest[end..])
+    }
+}
+
+/// The clients and policies shared by the requests of every page.
+struct Pager<C> {
+    client: C,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+    credentials: Option<Arc<dyn CredentialProvider>>,
+    converters: Converters,
+    retry: Option<Retry>,
+    pagination: Pagination,
+}
+
+/// The state of the pages, the request of the first page is the template of the following pages.
+struct State<R, T> {
+    request: Option<R>,
+    error: Option<Error>,
+    next: Option<String>,
+    items: VecDeque<T>,
+}
+
+impl<R, T> State<R, T> {
+    fn new<B: Backend>(req: Result<R>, pagination: &Pagination) -> Self
+    where
+        R: Request<B>,
+    {
+        let first = req.and_then(|req| pagination.first(req.url()).map(|url| (req, url)));
+        let (request, next, error) = match first {
+            Ok((req, url)) => (Some(req), Some(url), None),
+            Err(err) => (None, None, Some(err)),
+        };
+
+        State {
+            request,
+            error,
+            next,
+            items: VecDeque::new(),
+        }
+    }
+
+    /// Returns the request of the next page.
+    ///
+    /// The next page of another origin is returned as `Error::Request`,
+    /// so the credentials and default headers of the service are never sent to it.
+    fn next_request<B: Backend>(&mut self) -> Option<Result<(R, String)>>
+    where
+        R: Request<B>,
+    {
+        if let Some(err) = self.error.take() {
+            return Some(Err(err));
+        }
+
+        let url = self.next.take()?;
+        let req = self
+            .request
+            .as_ref()
+            .and_then(Request::try_clone)
+            .ok_or_else(|| {
+                Error::Request("the request of the next page could not be cloned".into())
+            })
+            .and_then(|mut req| {
+                if !same_origin(req.url(), &url) {
+                    return Err(Error::Request(
+                        format!("the next page `{}` is of another origin", url).into(),
+                    ));
+                }
+
+                req.set_url(&url).map(|_| (req, url))
+            });
+
+        Some(req)
+    }
+}
+
+/// The items of a paginated method, which follows the next page on demand.
+///
+/// The async `Pages` is a `Stream` of the items.
+pub struct Pages<B, C: AsyncClient<B>, T, E = Infallible>
+where
+    B: Backend,
+{
+    pager: Arc<Pager<C>>,
+    state: State<C::Request, T>,
+    pending: Option<BoxFuture<'static, Result<Page<T>>>>,
+    error: fn(Error, &Converters) -> Error<E>,
+    backend: PhantomData<fn() -> B>,
+}
+
+impl<B, C, T, E> Pages<B, C, T, E>
+where
+    B: Backend,
+    C: AsyncClient<B>,
+{
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        client: C,
+        req: Result<C::Request>,
+        interceptors: Vec<Arc<dyn Interceptor>>,
+        credentials: Option<Arc<dyn CredentialProvider>>,
+        converters: Converters,
+        retry: Option<Retry>,
+        pagination: Pagination,
+        error: fn(Error, &Converters) -> Error<E>,
+    ) -> Self {
+        Pages {
+            state: State::new::<B>(req, &pagination),
+            pager: Arc::new(Pager {
+                client,
+                interceptors,
+                credentials,
+                converters,
+                retry,
+                pagination,
+            }),
+            pending: None,
+            error,
+            backend: PhantomData,
+        }
+    }
+}
+
+impl<B, C, T, E> Unpin for Pages<B, C, T, E>
+where
+    B: Backend,
+    C: AsyncClient<B>,
+{
+}
+
+impl<B, C, T, E> Stream for Pages<B, C, T, E>
+where
+    B: Backend + 'static,
+    C: AsyncClient<B> + Send + Sync + 'static,
+    C::Request: Send + 'static,
+    C::Response: Send,
+    T: DeserializeOwned + Send + 'static,
+{
+    type Item = Result<T, Error<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(item) = this.state.items.pop_front() {
+                return Poll::Ready(Some(Ok(item)));
+            }
+
+            if let Some(ref mut pending) = this.pending {
+                let page = match pending.as_mut().poll(cx) {
+                    Poll::Ready(page) => page,
+                    Poll::Pending => return Poll::Pending,
+                };
+
+                this.pending = None;
+
+                match page {
+                    Ok((items, next)) => {
+                        this.state.items = items.into();
+                        this.state.next = next;
+
+                        continue;
+                    }
+                    Err(err) => {
+                        return Poll::Ready(Some(Err((this.error)(err, &this.pager.converters))))
+                    }
+                }
+            }
+
+            let (req, url) = match this.state.next_request::<B>() {
+                Some(Ok(next)) => next,
+                Some(Err(err)) => {
+                    return Poll::Ready(Some(Err((this.error)(err, &this.pager.converters))))
+                }
+                None => return Poll::Ready(None),
+            };
+            let pager = this.pager.clone();
+
+            tracing::debug!(%url, "fetch page");
+
+            this.pending = Some(Box::pin(async move {
+                let res = backend::execute::<B, C>(
+                    &pager.client,
+                    Ok(req),
+                    &pager.interceptors,
+                    pager.credentials.as_deref(),
+                    pager.retry.as_ref(),
+                )
+                .await?;
+                let headers = res.headers().clone();
+                let body = res.bytes().await.map_err(B::error)?;
+
+                pager.pagination.page(&url, &headers, &body)
+            }));
+        }
+    }
+}
+
+pub mod blocking {
+    //! The blocking pagination.
+    use super::*;
+    use crate::backend::blocking::{self, Client, Response as _};
+
+    /// The items of a paginated method, which follows the next page on demand.
+    ///
+    /// The blocking `Pages` is an `Iterator` of the items.
+    pub struct Pages<B, C: Client<B>, T, E = Infallible>
+    where
+        B: Backend,
+    {
+        pager: Pager<C>,
+        state: State<C::Request, T>,
+        error: fn(Error, &Converters) -> Error<E>,
+        backend: PhantomData<fn() -> B>,
+    }
+
+    impl<B, C, T, E> Pages<B, C, T, E>
+    where
+        B: Backend,
+        C: Client<B>,
+    {
+        #[allow(clippy::too_many_arguments)]
+        pub fn new(
+            client: C,
+            req: Result<C::Request>,
+            interceptors: Vec<Arc<dyn Interceptor>>,
+            credentials: Option<Arc<dyn CredentialProvider>>,
+            converters: Converters,
+            retry: Option<Retry>,
+            pagination: Pagination,
+            error: fn(Error, &Converters) -> Error<E>,
+        ) -> Self {
+            Pages {
+                state: State::new::<B>(req, &pagination),
+                pager: Pager {
+                    client,
+                    interceptors,
+                    credentials,
+                    converters,
+                    retry,
+                    pagination,
+                },
+                error,
+                backend: PhantomData,
+            }
+        }
+
+        fn fetch(&self, req: C::Request, url: &str) -> Result<Page<T>>
+        where
+            T: DeserializeOwned,
+        {
+            let pager = &self.pager;
+            let res = blocking::execute::<B, C>(
+                &pager.client,
+                Ok(req),
+                &pager.interceptors,
+                pager.credentials.as_deref(),
+                pager.retry.as_ref(),
+            )?;
+            let headers = res.headers().clone();
+            let body = res.bytes().map_err(B::error)?;
+
+            pager.pagination.page(url, &headers, &body)
+        }
+    }
+
+    impl<B, C, T, E> Iterator for Pages<B, C, T, E>
+    where
+        B: Backend,
+        C: Client<B>,
+        T: DeserializeOwned,
+    {
+        type Item = Result<T, Error<E>>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                if let Some(item) = self.state.items.pop_front() {
+                    return Some(Ok(item));
+                }
+
+                let page = self.state.next_request::<B>()?.and_then(|(req, url)| {
+                    tracing::debug!(%url, "fetch page");
+
+                    self.fetch(req, &url)
+                });
+
+                match page {
+                    Ok((items, next)) => {
+                        self.state.items = items.into();
+                        self.state.next = next;
+                    }
+                    Err(err) => return Some(Err((self.error)(err, &self.pager.converters))),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::time::Duration;
+
+    use http::{HeaderValue, Method};
+
+    use super::*;
+
+    struct Fake;
+
+    impl Backend for Fake {
+        type Error = io::Error;
+
+        fn error(err: Self::Error) -> Error {
+            Error::Transport(err.into())
+        }
+
+        fn sleep(_: Duration) -> BoxFuture<'static, ()> {
+            Box::pin(async {})
+        }
+    }
+
+    #[derive(Clone)]
+    struct Req(Method, String, HeaderMap);
+
+    impl Request<Fake> for Req {
+        fn method(&self) -> &Method {
+            &self.0
+        }
+
+        fn method_mut(&mut self) -> &mut Method {
+            &mut self.0
+        }
+
+        fn url(&self) -> &str {
+            &self.1
+        }
+
+        fn set_url(&mut self, url: &str) -> Result<()> {
+            self.1 = url.to_string();
+            Ok(())
+        }
+
+        fn headers_mut(&mut self) -> &mut HeaderMap {
+            &mut self.2
+        }
+
+        fn try_clone(&self) -> Option<Self> {
+            Some(self.clone())
+        }
+    }
+
+    fn link(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(LINK, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn cursor() -> Pagination {
+        Pagination::Cursor {
+            query: "cursor",
+            next: "/next",
+            items: "/items",
+        }
+    }
+
+    fn offset() -> Pagination {
+        Pagination::Offset {
+            offset: "offset",
+            limit: "limit",
+            size: 2,
+        }
+    }
+
+    #[test]
+    fn parse_next_link() {
+        assert_eq!(next_link(&HeaderMap::new()), None);
+        assert_eq!(
+            next_link(&link(&[
+                r#"<https://api.github.com/user/repos?page=2>; rel="next", <https://api.github.com/user/repos?page=5>; rel="last""#
+            ])),
+            Some("https://api.github.com/user/repos?page=2".into())
+        );
+        assert_eq!(
+            next_link(&link(&[
+                r#"<https://example.com/items?page=1>; rel="prev""#,
+                r#"<https://example.com/items?page=3>; rel=next"#
+            ])),
+            Some("https://example.com/items?page=3".into())
+        );
+        assert_eq!(
+            next_link(&link(&[
+                r#"<https://example.com/items?ids=1,2;3&page=2>; title="a, b; c"; REL="last next""#
+            ])),
+            Some("https://example.com/items?ids=1,2;3&page=2".into())
+        );
+        assert_eq!(
+            next_link(&link(&[
+                r#"<https://example.com/a>; rel="prev" junk, <https://example.com/b>; rel="next""#
+            ])),
+            Some("https://example.com/b".into())
+        );
+        assert_eq!(
+            next_link(&link(&[r#"<https://example.com/a>; rel="prev""#])),
+            None
+        );
+        assert_eq!(next_link(&link(&["https://example.com/a; rel=next"])), None);
+    }
+
+    #[test]
+    fn parse_link_params() {
+        assert_eq!(
+            links(r#"<a>; rel="next\"x"; title=b, <c>"#),
+            vec![("a", r#"next"x"#.to_string()), ("c", String::new())]
+        );
+        assert_eq!(link_param(" rel = next ; x"), ("rel", "next".into(), "; x"));
+        assert_eq!(
+            link_param("crossorigin, <b>"),
+            ("crossorigin", "".into(), ", <b>")
+        );
+    }
+
+    #[test]
+    fn replace_query() {
+        assert_eq!(
+            set_query(
+                "https://example.com/items?page=1&sort=name",
+                &[("page", "2".into())]
+            )
+            .unwrap(),
+            "https://example.com/items?sort=name&page=2"
+        );
+        assert_eq!(
+            set_query("https://example.com/items", &[("q", "a b&c".into())]).unwrap(),
+            "https://example.com/items?q=a+b%26c"
+        );
+        assert!(set_query("/items", &[("page", "2".into())]).is_err());
+    }
+
+    #[test]
+    fn link_header_page() {
+        let (items, next) = Pagination::LinkHeader
+            .page::<u32>(
+                "https://example.com/items?page=1",
+                &link(&[r#"</items?page=2>; rel="next""#]),
+                b"[1, 2]",
+            )
+            .unwrap();
+
+        assert_eq!(items, vec![1, 2]);
+        assert_eq!(next.as_deref(), Some("https://example.com/items?page=2"));
+
+        let (_, next) = Pagination::LinkHeader
+            .page::<u32>("https://example.com/items?page=2", &HeaderMap::new(), b"[]")
+            .unwrap();
+
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn stop_at_link_to_current_page() {
+        for target in ["/items?page=2", "https://example.com:443/items?page=2"] {
+            let (items, next) = Pagination::LinkHeader
+                .page::<u32>(
+                    "https://example.com/items?page=2",
+                    &link(&[&format!(r#"<{}>; rel="next""#, target)]),
+                    b"[3]",
+                )
+                .unwrap();
+
+            assert_eq!(items, vec![3]);
+            assert_eq!(next, None);
+        }
+    }
+
+    #[test]
+    fn cursor_page() {
+        let url = "https://example.com/items?limit=2";
+        let (items, next) = cursor()
+            .page::<u32>(
+                url,
+                &HeaderMap::new(),
+                br#"{"items": [1, 2], "next": "abc"}"#,
+            )
+            .unwrap();
+
+        assert_eq!(items, vec![1, 2]);
+        assert_eq!(
+            next.as_deref(),
+            Some("https://example.com/items?limit=2&cursor=abc")
+        );
+
+        let (_, next) = cursor()
+            .page::<u32>(url, &HeaderMap::new(), br#"{"items": [], "next": 42}"#)
+            .unwrap();
+
+        assert_eq!(
+            next.as_deref(),
+            Some("https://example.com/items?limit=2&cursor=42")
+        );
+
+        for body in [
+            &br#"{"items": [3], "next": ""}"#[..],
+            br#"{"items": [3], "next": null}"#,
+            br#"{"items": [3]}"#,
+        ] {
+            let (items, next) = cursor().page::<u32>(url, &HeaderMap::new(), body).unwrap();
+
+            assert_eq!(items, vec![3]);
+            assert_eq!(next, None);
+        }
+
+        let (items, next) = cursor()
+            .page::<u32>(url, &HeaderMap::new(), br#"{"next": "abc"}"#)
+            .unwrap();
+
+        assert!(items.is_empty());
+        assert!(next.is_some());
+    }
+
+    #[test]
+    fn stop_at_repeated_cursor() {
+        let (items, next) = cursor()
+            .page::<u32>(
+                "https://example.com/items?cursor=abc",
+                &HeaderMap::new(),
+                br#"{"items": [1], "next": "abc"}"#,
+            )
+            .unwrap();
+
+        assert_eq!(items, vec![1]);
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn offset_page() {
+        let first = offset()
+            .first("https://example.com/items?sort=name")
+            .unwrap();
+
+        assert_eq!(
+            first,
+            "https://example.com/items?sort=name&offset=0&limit=2"
+        );
+
+        let (items, next) = offset()
+            .page::<u32>(&first, &HeaderMap::new(), b"[1, 2]")
+            .unwrap();
+
+        assert_eq!(items, vec![1, 2]);
+        assert_eq!(
+            next.as_deref(),
+            Some("https://example.com/items?sort=name&limit=2&offset=2")
+        );
+
+        let (items, next) = offset()
+            .page::<u32>(next.as_deref().unwrap(), &HeaderMap::new(), b"[3]")
+            .unwrap();
+
+        assert_eq!(items, vec![3]);
+        assert_eq!(next, None);
+
+        let (_, next) = offset()
+            .page::<u32>(&first, &HeaderMap::new(), b"[]")
+            .unwrap();
+
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn decode_error() {
+        assert!(matches!(
+            offset().page::<u32>("https://example.com/", &HeaderMap::new(), b"{}"),
+            Err(Error::Deserialize(_))
+        ));
+    }
+
+    #[test]
+    fn refuse_another_origin() {
+        let req = Req(
+            Method::GET,
+            "https://example.com/items".into(),
+            HeaderMap::new(),
+        );
+        let mut state = State::<_, u32>::new::<Fake>(Ok(req), &Pagination::LinkHeader);
+
+        let (req, url) = state.next_request::<Fake>().unwrap().unwrap();
+        assert_eq!(url, "https://example.com/items");
+        assert_eq!(req.1, url);
+
+        state.next = Some("https://example.com:443/items?page=2".into());
+        assert!(state.next_request::<Fake>().unwrap().is_ok());
+
+        state.next = Some("https://evil.example.com/items?page=3".into());
+        assert!(matches!(
+            state.next_request::<Fake>(),
+            Some(Err(Error::Request(_)))
+        ));
+
+        assert!(state.next_request::<Fake>().is_none());
+    }
+}
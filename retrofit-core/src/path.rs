@@ -0,0 +1,68 @@
+//! Percent-encoding of the path parameters.
+use std::fmt::Display;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+use crate::{Error, Result};
+
+/// The characters which must be percent-encoded in a path segment.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'\\')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Percent-encode the formatted value as a single path segment, e.g. `feature/a b` as `feature%2Fa%20b`.
+///
+/// The `.` and `..` segments are returned as `Error::Request`, since they are removed from the URL
+/// even if percent-encoded.
+pub fn encode<T: Display + ?Sized>(value: &T) -> Result<String> {
+    let value = value.to_string();
+
+    if value == "." || value == ".." {
+        return Err(Error::Request(
+            format!("`{}` is not a valid path segment", value).into(),
+        ));
+    }
+
+    Ok(utf8_percent_encode(&value, PATH_SEGMENT).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_segment() {
+        let encode = |value: &dyn Display| encode(value).unwrap();
+
+        assert_eq!(encode(&"feature/a b"), "feature%2Fa%20b");
+        assert_eq!(encode(&"a?b#c%d"), "a%3Fb%23c%25d");
+        assert_eq!(encode(&"{x}<y>\"z\"`"), "%7Bx%7D%3Cy%3E%22z%22%60");
+        assert_eq!(encode(&"a\\b"), "a%5Cb");
+        assert_eq!(encode(&"café"), "caf%C3%A9");
+        assert_eq!(encode(&42), "42");
+        assert_eq!(encode(&"..."), "...");
+        assert_eq!(encode(&".a"), ".a");
+    }
+
+    #[test]
+    fn reject_dot_segments() {
+        assert!(matches!(super::encode("."), Err(Error::Request(_))));
+        assert!(matches!(super::encode(".."), Err(Error::Request(_))));
+    }
+
+    #[test]
+    fn keep_unreserved_and_sub_delims() {
+        assert_eq!(encode("a-b_c.d~e").unwrap(), "a-b_c.d~e");
+        assert_eq!(encode("a+b,c;d=e:f@g&h!").unwrap(), "a+b,c;d=e:f@g&h!");
+    }
+}
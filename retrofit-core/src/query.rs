@@ -0,0 +1,109 @@
+//! The individual query parameters.
+use serde::ser::{Error as _, Serialize, SerializeSeq, Serializer};
+use serde_json::Value;
+
+/// How a sequence value is encoded in the query string.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Style {
+    /// Repeats the key for every element, e.g. `id=1&id=2`.
+    #[default]
+    Multi,
+    /// Joins the elements with comma, e.g. `id=1,2`.
+    Csv,
+}
+
+/// A named query parameter.
+///
+/// The `None` value is omitted, and a sequence is expanded per the `Style`.
+#[derive(Debug)]
+pub struct Query<'a, T: ?Sized> {
+    name: &'a str,
+    value: &'a T,
+    style: Style,
+}
+
+impl<'a, T: ?Sized> Query<'a, T> {
+    /// Creates a query parameter with the name and value.
+    pub fn new(name: &'a str, value: &'a T, style: Style) -> Self {
+        Query { name, value, style }
+    }
+}
+
+impl<'a, T: Serialize + ?Sized> Serialize for Query<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let values = match serde_json::to_value(self.value).map_err(S::Error::custom)? {
+            Value::Null => vec![],
+            Value::Array(values) => values
+                .into_iter()
+                .filter(|value| !value.is_null())
+                .map(scalar)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(S::Error::custom)?,
+            value => vec![scalar(value).map_err(S::Error::custom)?],
+        };
+
+        let pairs = match self.style {
+            Style::Multi => values,
+            Style::Csv if values.is_empty() => values,
+            Style::Csv => vec![values.join(",")],
+        };
+
+        let mut seq = serializer.serialize_seq(Some(pairs.len()))?;
+        for value in pairs {
+            seq.serialize_element(&(self.name, value))?;
+        }
+        seq.end()
+    }
+}
+
+fn scalar(value: Value) -> Result<String, &'static str> {
+    match value {
+        Value::String(s) => Ok(s),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        _ => Err("query parameter should be a scalar, an option or a sequence of scalars"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode<T: Serialize + ?Sized>(name: &str, value: &T, style: Style) -> String {
+        serde_urlencoded::to_string(Query::new(name, value, style)).unwrap()
+    }
+
+    #[test]
+    fn scalar_value() {
+        assert_eq!(encode("q", "a b", Style::Multi), "q=a+b");
+        assert_eq!(encode("page", &2, Style::Multi), "page=2");
+        assert_eq!(encode("all", &true, Style::Csv), "all=true");
+    }
+
+    #[test]
+    fn optional_value() {
+        assert_eq!(encode("page", &Some(2), Style::Multi), "page=2");
+        assert_eq!(encode("page", &None::<u32>, Style::Multi), "");
+        assert_eq!(encode("page", &None::<u32>, Style::Csv), "");
+    }
+
+    #[test]
+    fn sequence_value() {
+        assert_eq!(encode("id", &[1, 2, 3], Style::Multi), "id=1&id=2&id=3");
+        assert_eq!(encode("id", &[1, 2, 3], Style::Csv), "id=1%2C2%2C3");
+        assert_eq!(
+            encode("id", &[Some(1), None, Some(3)], Style::Multi),
+            "id=1&id=3"
+        );
+        assert_eq!(encode("id", &Vec::<u32>::new(), Style::Multi), "");
+        assert_eq!(encode("id", &Vec::<u32>::new(), Style::Csv), "");
+    }
+
+    #[test]
+    fn reject_nested_value() {
+        let value = serde_json::json!({ "a": 1 });
+
+        assert!(serde_urlencoded::to_string(Query::new("q", &value, Style::Multi)).is_err());
+        assert!(serde_urlencoded::to_string(Query::new("q", &[[1]], Style::Multi)).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+//! The decoded response with its metadata.
+use http::{HeaderMap, StatusCode};
+
+use crate::backend::{self, Backend};
+
+/// The decoded body of a response with the status, headers and final URL,
+/// declared with `-> retrofit::Response<T>`.
+#[derive(Clone, Debug)]
+pub struct Response<T> {
+    status: StatusCode,
+    headers: HeaderMap,
+    url: String,
+    body: T,
+}
+
+impl Response<()> {
+    /// Takes the status, headers and final URL of the backend response before the body is decoded.
+    pub fn head<B: Backend, R: backend::Response<B>>(res: &R) -> Self {
+        Response {
+            status: res.status(),
+            headers: res.headers().clone(),
+            url: res.url().to_string(),
+            body: (),
+        }
+    }
+}
+
+impl<T> Response<T> {
+    /// Get the `StatusCode` of this `Response`.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Get the `Headers` of this `Response`.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Get the final `Url` of this `Response`, after the redirects.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Get the decoded body of this `Response`.
+    pub fn body(&self) -> &T {
+        &self.body
+    }
+
+    /// Consumes the `Response`, returning the decoded body.
+    pub fn into_body(self) -> T {
+        self.body
+    }
+
+    /// Maps the decoded body, keeping the status, headers and URL.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Response<U> {
+        Response {
+            status: self.status,
+            headers: self.headers,
+            url: self.url,
+            body: f(self.body),
+        }
+    }
+}
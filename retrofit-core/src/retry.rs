@@ -0,0 +1,396 @@
+//! The retry policy of the requests.
+use std::future::Future;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use http::{header::RETRY_AFTER, HeaderMap, StatusCode};
+
+use crate::{
+    backend::{Backend, Response},
+    Error, Result,
+};
+
+/// The backoff strategy between the attempts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Backoff {
+    /// Retry immediately.
+    None,
+    /// Wait the same delay before every attempt.
+    Constant,
+    /// Double the delay after every attempt.
+    Exponential,
+}
+
+/// The retry policy of a request, declared with `#[retry(...)]`.
+///
+/// A request is retried on the transport errors and the listed status codes,
+/// the `Retry-After` header of the response takes precedence over the backoff delay.
+#[derive(Clone, Copy, Debug)]
+pub struct Retry {
+    max: u32,
+    backoff: Backoff,
+    delay: Duration,
+    max_delay: Duration,
+    on: &'static [u16],
+}
+
+impl Retry {
+    /// Retry the request at most `max` times, with exponential backoff on `502`, `503` and `504`.
+    pub const fn new(max: u32) -> Self {
+        Retry {
+            max,
+            backoff: Backoff::Exponential,
+            delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(30),
+            on: &[502, 503, 504],
+        }
+    }
+
+    /// Sets the backoff strategy between the attempts.
+    pub const fn backoff(self, backoff: Backoff) -> Self {
+        Retry { backoff, ..self }
+    }
+
+    /// Sets the initial delay between the attempts.
+    pub const fn delay(self, delay: Duration) -> Self {
+        Retry { delay, ..self }
+    }
+
+    /// Sets the maximum delay between the attempts.
+    pub const fn max_delay(self, max_delay: Duration) -> Self {
+        Retry { max_delay, ..self }
+    }
+
+    /// Sets the status codes to retry on.
+    pub const fn on(self, on: &'static [u16]) -> Self {
+        Retry { on, ..self }
+    }
+
+    /// Returns the delay before the next attempt if the response should be retried.
+    pub fn on_status(
+        &self,
+        attempt: u32,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<Duration> {
+        if attempt >= self.max || !self.on.contains(&status.as_u16()) {
+            return None;
+        }
+
+        Some(retry_after(headers).map_or_else(
+            || self.backoff_delay(attempt),
+            |delay| delay.min(self.max_delay),
+        ))
+    }
+
+    /// Returns the delay before the next attempt if the error should be retried.
+    pub fn on_error(&self, attempt: u32, err: &Error) -> Option<Duration> {
+        if attempt < self.max && err.is_transport() {
+            Some(self.backoff_delay(attempt))
+        } else {
+            None
+        }
+    }
+
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        match self.backoff {
+            Backoff::None => Duration::from_secs(0),
+            Backoff::Constant => self.delay,
+            Backoff::Exponential => self
+                .delay
+                .checked_mul(1 << attempt.min(16))
+                .map_or(self.max_delay, |delay| delay.min(self.max_delay)),
+        }
+    }
+
+    /// Sends the request with the blocking client until it succeeds or the attempts are exhausted.
+    ///
+    /// The request is sent once if it could not be cloned, e.g. with a streaming body.
+    pub fn blocking_send<B, Q, R>(
+        &self,
+        mut req: Q,
+        try_clone: impl Fn(&Q) -> Option<Q>,
+        mut send: impl FnMut(Q) -> Result<R>,
+    ) -> Result<R>
+    where
+        B: Backend,
+        R: Response<B>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            let next = match try_clone(&req) {
+                Some(next) => next,
+                None => return send(req),
+            };
+            let delay = match send(req) {
+                Ok(res) => match self.on_status(attempt, res.status(), res.headers()) {
+                    Some(delay) => delay,
+                    None => return Ok(res),
+                },
+                Err(err) => match self.on_error(attempt, &err) {
+                    Some(delay) => delay,
+                    None => return Err(err),
+                },
+            };
+
+            tracing::debug!(attempt, ?delay, "retry request");
+
+            thread::sleep(delay);
+
+            req = next;
+            attempt += 1;
+        }
+    }
+
+    /// Sends the request with the async client until it succeeds or the attempts are exhausted.
+    ///
+    /// The request is sent once if it could not be cloned, e.g. with a streaming body.
+    pub async fn send<B, Q, R, F>(
+        &self,
+        mut req: Q,
+        try_clone: impl Fn(&Q) -> Option<Q>,
+        mut send: impl FnMut(Q) -> F,
+    ) -> Result<R>
+    where
+        B: Backend,
+        R: Response<B>,
+        F: Future<Output = Result<R>>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            let next = match try_clone(&req) {
+                Some(next) => next,
+                None => return send(req).await,
+            };
+            let delay = match send(req).await {
+                Ok(res) => match self.on_status(attempt, res.status(), res.headers()) {
+                    Some(delay) => delay,
+                    None => return Ok(res),
+                },
+                Err(err) => match self.on_error(attempt, &err) {
+                    Some(delay) => delay,
+                    None => return Err(err),
+                },
+            };
+
+            tracing::debug!(attempt, ?delay, "retry request");
+
+            B::sleep(delay).await;
+
+            req = next;
+            attempt += 1;
+        }
+    }
+}
+
+/// Parses the `Retry-After` header, in seconds or a HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(secs) = value.parse() {
+        Some(Duration::from_secs(secs))
+    } else {
+        httpdate::parse_http_date(value)
+            .ok()?
+            .duration_since(SystemTime::now())
+            .ok()
+            .or_else(|| Some(Duration::from_secs(0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::io;
+    use std::task::{Context, Poll, Waker};
+
+    use http::HeaderValue;
+
+    use super::*;
+    use crate::backend::BoxFuture;
+
+    struct Fake;
+
+    impl Backend for Fake {
+        type Error = io::Error;
+
+        fn error(err: Self::Error) -> Error {
+            Error::Transport(err.into())
+        }
+
+        fn sleep(_: Duration) -> BoxFuture<'static, ()> {
+            Box::pin(async {})
+        }
+    }
+
+    struct Res(StatusCode, HeaderMap);
+
+    impl Response<Fake> for Res {
+        fn status(&self) -> StatusCode {
+            self.0
+        }
+
+        fn headers(&self) -> &HeaderMap {
+            &self.1
+        }
+
+        fn headers_mut(&mut self) -> &mut HeaderMap {
+            &mut self.1
+        }
+
+        fn url(&self) -> &str {
+            "http://example.com"
+        }
+    }
+
+    fn headers(retry_after: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(retry_after).unwrap());
+        headers
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let mut cx = Context::from_waker(Waker::noop());
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    /// Sends the request, which fails with the statuses in order, and returns the number of attempts.
+    fn attempts(retry: Retry, cloneable: bool, statuses: &[u16]) -> (usize, StatusCode) {
+        let sent = Cell::new(0);
+        let send = |_: ()| {
+            let status = statuses[sent.get().min(statuses.len() - 1)];
+            sent.set(sent.get() + 1);
+            Ok(Res(StatusCode::from_u16(status).unwrap(), HeaderMap::new()))
+        };
+        let try_clone = |_: &()| if cloneable { Some(()) } else { None };
+        let res = retry
+            .blocking_send::<Fake, _, _>((), try_clone, send)
+            .unwrap();
+
+        (sent.get(), res.0)
+    }
+
+    #[test]
+    fn parse_retry_after() {
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+        assert_eq!(retry_after(&headers("120")), Some(Duration::from_secs(120)));
+        assert_eq!(retry_after(&headers(" 3 ")), Some(Duration::from_secs(3)));
+        assert_eq!(retry_after(&headers("soon")), None);
+        assert_eq!(
+            retry_after(&headers("Wed, 21 Oct 2015 07:28:00 GMT")),
+            Some(Duration::from_secs(0))
+        );
+
+        let later = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(60));
+        let delay = retry_after(&headers(&later)).unwrap();
+        assert!(delay > Duration::from_secs(55) && delay <= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn backoff_delays() {
+        let retry = Retry::new(3).delay(Duration::from_millis(100));
+
+        assert_eq!(retry.backoff_delay(0), Duration::from_millis(100));
+        assert_eq!(retry.backoff_delay(3), Duration::from_millis(800));
+        assert_eq!(retry.backoff_delay(100), Duration::from_secs(30));
+        assert_eq!(
+            retry.max_delay(Duration::from_millis(500)).backoff_delay(3),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            retry.backoff(Backoff::Constant).backoff_delay(3),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            retry.backoff(Backoff::None).backoff_delay(3),
+            Duration::from_secs(0)
+        );
+    }
+
+    #[test]
+    fn retry_on_status() {
+        let retry = Retry::new(2).backoff(Backoff::Constant);
+        let unavailable = StatusCode::SERVICE_UNAVAILABLE;
+
+        assert_eq!(
+            retry.on_status(0, unavailable, &HeaderMap::new()),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            retry.on_status(1, unavailable, &headers("5")),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            retry.on_status(1, unavailable, &headers("3600")),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(retry.on_status(2, unavailable, &HeaderMap::new()), None);
+        assert_eq!(
+            retry.on_status(0, StatusCode::NOT_FOUND, &HeaderMap::new()),
+            None
+        );
+        assert_eq!(
+            retry
+                .on(&[429])
+                .on_status(0, StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new()),
+            Some(Duration::from_millis(100))
+        );
+    }
+
+    #[test]
+    fn retry_on_error() {
+        let retry = Retry::new(1).backoff(Backoff::None);
+
+        assert_eq!(
+            retry.on_error(0, &Error::Transport("reset".into())),
+            Some(Duration::from_secs(0))
+        );
+        assert_eq!(retry.on_error(1, &Error::Transport("reset".into())), None);
+        assert_eq!(retry.on_error(0, &Error::Request("invalid".into())), None);
+    }
+
+    #[test]
+    fn limit_attempts() {
+        let retry = Retry::new(2).backoff(Backoff::None);
+
+        assert_eq!(attempts(retry, true, &[200]), (1, StatusCode::OK));
+        assert_eq!(attempts(retry, true, &[503, 200]), (2, StatusCode::OK));
+        assert_eq!(
+            attempts(retry, true, &[503]),
+            (3, StatusCode::SERVICE_UNAVAILABLE)
+        );
+        assert_eq!(
+            attempts(retry, false, &[503]),
+            (1, StatusCode::SERVICE_UNAVAILABLE)
+        );
+    }
+
+    #[test]
+    fn retry_transport_errors() {
+        let retry = Retry::new(3).backoff(Backoff::None);
+        let sent = Cell::new(0);
+        let send = |_: ()| {
+            sent.set(sent.get() + 1);
+
+            async {
+                if sent.get() < 3 {
+                    Err(Error::Transport("reset".into()))
+                } else {
+                    Ok(Res(StatusCode::OK, HeaderMap::new()))
+                }
+            }
+        };
+        let res = block_on(retry.send::<Fake, _, _, _>((), |_| Some(()), send)).unwrap();
+
+        assert_eq!(res.0, StatusCode::OK);
+        assert_eq!(sent.get(), 3);
+    }
+}
@@ -0,0 +1,177 @@
+//! The request bodies streamed on demand, with the progress of the upload.
+use std::fmt;
+use std::io::{self, Read};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use tokio::io::AsyncRead;
+
+/// Reports the progress of an upload as the chunks of the body are read by the client.
+///
+/// The `Progress` of a `Read` is a `Read` and the `Progress` of a `Stream` is a `Stream`,
+/// see `Upload::on_progress` to observe the upload of a request body.
+pub struct Progress<T> {
+    inner: T,
+    sent: u64,
+    callback: Box<dyn FnMut(u64) + Send + Sync>,
+}
+
+impl<T> Progress<T> {
+    /// Wraps the reader or stream of the body, the callback is called with the bytes sent so far.
+    pub fn new<F>(inner: T, callback: F) -> Self
+    where
+        F: FnMut(u64) + Send + Sync + 'static,
+    {
+        Progress {
+            inner,
+            sent: 0,
+            callback: Box::new(callback),
+        }
+    }
+
+    /// Returns the bytes sent so far.
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+
+    fn advance(&mut self, len: usize) {
+        if len > 0 {
+            self.sent += len as u64;
+
+            (self.callback)(self.sent);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Progress<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("inner", &self.inner)
+            .field("sent", &self.sent)
+            .finish()
+    }
+}
+
+impl<R: Read> Read for Progress<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+
+        self.advance(len);
+
+        Ok(len)
+    }
+}
+
+impl<S, E> Stream for Progress<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let chunk = Pin::new(&mut this.inner).poll_next(cx);
+
+        if let Poll::Ready(Some(Ok(ref chunk))) = chunk {
+            this.advance(chunk.len());
+        }
+
+        chunk
+    }
+}
+
+/// A request body streamed on demand, declared with `#[body(upload)]` and `Self::Upload`.
+///
+/// The async `Upload` streams the body from a `Stream` of `Bytes` chunks or from an `AsyncRead`,
+/// e.g. a `tokio::fs::File`.
+pub struct Upload {
+    stream: Pin<Box<dyn Stream<Item = io::Result<Bytes>> + Send + Sync>>,
+}
+
+impl Upload {
+    /// Streams the body from the chunks of a `Stream`.
+    pub fn new<S>(stream: S) -> Self
+    where
+        S: Stream<Item = io::Result<Bytes>> + Send + Sync + 'static,
+    {
+        Upload {
+            stream: Box::pin(stream),
+        }
+    }
+
+    /// Streams the body from the chunks read from an `AsyncRead`.
+    pub fn from_reader<R>(reader: R) -> Self
+    where
+        R: AsyncRead + Send + Sync + 'static,
+    {
+        Upload::new(tokio::io::reader_stream(reader))
+    }
+
+    /// Reports the progress of the upload, the callback is called with the bytes sent so far.
+    pub fn on_progress<F>(self, callback: F) -> Self
+    where
+        F: FnMut(u64) + Send + Sync + 'static,
+    {
+        Upload::new(Progress::new(self.stream, callback))
+    }
+}
+
+impl fmt::Debug for Upload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Upload").finish()
+    }
+}
+
+impl Stream for Upload {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().stream.as_mut().poll_next(cx)
+    }
+}
+
+pub mod blocking {
+    //! The blocking request bodies.
+    use super::*;
+
+    /// A request body streamed on demand, declared with `#[body(upload)]` and `Self::Upload`.
+    ///
+    /// The blocking `Upload` streams the body from a `Read`, e.g. a `std::fs::File`.
+    pub struct Upload {
+        reader: Box<dyn Read + Send>,
+    }
+
+    impl Upload {
+        /// Streams the body from a `Read`.
+        pub fn new<R>(reader: R) -> Self
+        where
+            R: Read + Send + 'static,
+        {
+            Upload {
+                reader: Box::new(reader),
+            }
+        }
+
+        /// Reports the progress of the upload, the callback is called with the bytes sent so far.
+        pub fn on_progress<F>(self, callback: F) -> Self
+        where
+            F: FnMut(u64) + Send + Sync + 'static,
+        {
+            Upload::new(Progress::new(self.reader, callback))
+        }
+    }
+
+    impl fmt::Debug for Upload {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.debug_struct("Upload").finish()
+        }
+    }
+
+    impl Read for Upload {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reader.read(buf)
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_quote, Attribute, Error, ItemTrait, Result,
+};
+
+pub fn auth(_attr: Auth, item: ItemTrait) -> Result<TokenStream> {
+    Ok(item.into_token_stream())
+}
+
+/// The authentication scheme of the service, `#[auth(bearer)]` or `#[auth(basic)]`.
+#[derive(Clone, Debug)]
+pub enum Auth {
+    Bearer,
+    Basic,
+}
+
+impl Parse for Auth {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let scheme: Ident = input.parse()?;
+
+        if scheme == "bearer" {
+            Ok(Auth::Bearer)
+        } else if scheme == "basic" {
+            Ok(Auth::Basic)
+        } else {
+            Err(Error::new(
+                scheme.span(),
+                "expected `bearer` or `basic` scheme",
+            ))
+        }
+    }
+}
+
+impl Auth {
+    /// Extracts the authentication scheme of the service from `#[auth(...)]`.
+    pub fn extract(attrs: &[Attribute]) -> Result<Option<Self>> {
+        let path = parse_quote! { retrofit::auth };
+
+        attrs
+            .iter()
+            .find(|attr| attr.path.is_ident("auth") || attr.path == path)
+            .map(|attr| attr.parse_args())
+            .transpose()
+    }
+}
+
+impl ToTokens for Auth {
+    /// Generates the builder methods to set the credentials of the service.
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let scheme = match self {
+            Auth::Bearer => quote! {
+                /// Authenticates every request with the bearer token.
+                pub fn bearer_auth<T: Into<String>>(self, token: T) -> Self {
+                    self.credentials(retrofit::Credentials::Bearer(token.into()))
+                }
+            },
+            Auth::Basic => quote! {
+                /// Authenticates every request with the username and optional password.
+                pub fn basic_auth<U: Into<String>>(self, username: U, password: Option<String>) -> Self {
+                    self.credentials(retrofit::Credentials::Basic {
+                        username: username.into(),
+                        password,
+                    })
+                }
+            },
+        };
+
+        let expanded = quote! {
+            /// Supplies the credentials of every request,
+            /// the provider could refresh the credentials once a request was rejected with `401 Unauthorized`.
+            pub fn credentials<P: retrofit::CredentialProvider + 'static>(mut self, provider: P) -> Self {
+                self.credentials = Some(std::sync::Arc::new(provider));
+                self
+            }
+
+            #scheme
+        };
+
+        expanded.to_tokens(tokens);
+    }
+}
@@ -30,7 +30,7 @@ impl Deref for Headers {
 }
 
 impl Headers {
-    pub fn extract<'a>(name: &str, attrs: &'a [Attribute]) -> Result<Self> {
+    pub fn extract(name: &str, attrs: &[Attribute]) -> Result<Self> {
         let id = Ident::new(name, Span::call_site());
         let path = parse_quote! { retrofit::#id };
 
@@ -50,6 +50,7 @@ impl Headers {
 #[derive(Clone, Debug)]
 pub struct Header {
     pub name: Ident,
+    #[allow(dead_code)]
     pub eq_token: Token![=],
     pub value: Expr,
 }
@@ -1,9 +1,13 @@
 use proc_macro::TokenStream;
 use syn::parse::Error as ParseError;
 
+mod auth;
 mod header;
+mod paginate;
+mod param;
 mod request;
 mod response;
+mod retry;
 mod service;
 
 trait Output {
@@ -49,6 +53,14 @@ pub fn default_headers(attr: TokenStream, item: TokenStream) -> TokenStream {
     ))
 }
 
+#[proc_macro_attribute]
+pub fn auth(attr: TokenStream, item: TokenStream) -> TokenStream {
+    Output::process(auth::auth(
+        syn::parse(attr).expect("scheme"),
+        syn::parse(item).expect("trait"),
+    ))
+}
+
 #[proc_macro_attribute]
 pub fn get(attr: TokenStream, item: TokenStream) -> TokenStream {
     Output::process(request::request(
@@ -152,6 +164,14 @@ pub fn request(attr: TokenStream, item: TokenStream) -> TokenStream {
     ))
 }
 
+#[proc_macro_attribute]
+pub fn retry(attr: TokenStream, item: TokenStream) -> TokenStream {
+    Output::process(retry::retry(
+        syn::parse(attr).expect("args"),
+        syn::parse(item).expect("trait fn"),
+    ))
+}
+
 #[proc_macro_attribute]
 pub fn response(attr: TokenStream, item: TokenStream) -> TokenStream {
     Output::process(response::response(
@@ -159,3 +179,11 @@ pub fn response(attr: TokenStream, item: TokenStream) -> TokenStream {
         syn::parse(item).expect("trait fn"),
     ))
 }
+
+#[proc_macro_attribute]
+pub fn paginate(attr: TokenStream, item: TokenStream) -> TokenStream {
+    Output::process(paginate::paginate(
+        syn::parse(attr).expect("args"),
+        syn::parse(item).expect("trait fn"),
+    ))
+}
@@ -0,0 +1,131 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{parse_quote, spanned::Spanned, Attribute, Error, Expr, Result, TraitItemMethod};
+
+use crate::request::{Arg, Args};
+
+pub fn paginate(_attr: Args, item: TraitItemMethod) -> Result<TokenStream> {
+    Ok(item.into_token_stream())
+}
+
+/// The pagination of a list method, e.g. `link_header`, `cursor(query = "after")` or `offset(size = 100)`.
+#[derive(Clone, Debug)]
+pub enum Paginate {
+    /// Follows the `rel="next"` link of the `Link` header.
+    LinkHeader,
+    /// Sets the `query` parameter to the cursor at the `next` JSON pointer of the page.
+    Cursor {
+        query: Expr,
+        next: Expr,
+        items: Expr,
+    },
+    /// Sets the `offset` and `limit` query parameters.
+    Offset {
+        offset: Expr,
+        limit: Expr,
+        size: Expr,
+    },
+}
+
+impl Paginate {
+    /// Extracts the pagination of the method from `#[paginate(...)]`.
+    pub fn extract(attrs: &[Attribute]) -> Result<Option<(Self, Span)>> {
+        let path = parse_quote! { retrofit::paginate };
+
+        attrs
+            .iter()
+            .find(|attr| attr.path.is_ident("paginate") || attr.path == path)
+            .map(|attr| {
+                let args = attr.parse_args::<Args>()?;
+                let span = attr.path.span();
+
+                Paginate::new(args, span).map(|paginate| (paginate, span))
+            })
+            .transpose()
+    }
+
+    fn new(args: Args, span: Span) -> Result<Self> {
+        let mut args = args.into_iter();
+        let Arg {
+            ident, mut nested, ..
+        } = args
+            .next()
+            .ok_or_else(|| Error::new(span, "expected `link_header`, `cursor` or `offset`"))?;
+
+        if let Some(arg) = args.next() {
+            return Err(Error::new(arg.ident.span(), "expected one pagination"));
+        }
+
+        let mut options = |names: &[&str]| -> Result<Vec<Option<Expr>>> {
+            let mut values = vec![None; names.len()];
+
+            for Arg { ident, expr, .. } in nested.take().into_iter().flatten() {
+                match (names.iter().position(|name| ident == name), expr) {
+                    (Some(idx), Some(expr)) => values[idx] = Some(expr),
+                    _ if names.is_empty() => {
+                        return Err(Error::new(ident.span(), "expected no options"))
+                    }
+                    _ => {
+                        let (last, names) = names.split_last().unwrap();
+
+                        return Err(Error::new(
+                            ident.span(),
+                            format!("expected `{}` or `{}`", names.join("`, `"), last),
+                        ));
+                    }
+                }
+            }
+
+            Ok(values)
+        };
+
+        if ident == "link_header" {
+            options(&[])?;
+
+            Ok(Paginate::LinkHeader)
+        } else if ident == "cursor" {
+            let mut values = options(&["query", "next", "items"])?.into_iter();
+            let mut value = |default: Expr| values.next().flatten().unwrap_or(default);
+
+            Ok(Paginate::Cursor {
+                query: value(parse_quote! { "cursor" }),
+                next: value(parse_quote! { "/next" }),
+                items: value(parse_quote! { "/items" }),
+            })
+        } else if ident == "offset" {
+            let mut values = options(&["offset", "limit", "size"])?.into_iter();
+            let mut value = |default: Expr| values.next().flatten().unwrap_or(default);
+
+            Ok(Paginate::Offset {
+                offset: value(parse_quote! { "offset" }),
+                limit: value(parse_quote! { "limit" }),
+                size: value(parse_quote! { 30 }),
+            })
+        } else {
+            Err(Error::new(
+                ident.span(),
+                "expected `link_header`, `cursor` or `offset`",
+            ))
+        }
+    }
+}
+
+impl ToTokens for Paginate {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let expanded = match self {
+            Paginate::LinkHeader => quote! { retrofit::Pagination::LinkHeader },
+            Paginate::Cursor { query, next, items } => quote! {
+                retrofit::Pagination::Cursor { query: #query, next: #next, items: #items }
+            },
+            Paginate::Offset {
+                offset,
+                limit,
+                size,
+            } => quote! {
+                retrofit::Pagination::Offset { offset: #offset, limit: #limit, size: #size }
+            },
+        };
+
+        expanded.to_tokens(tokens);
+    }
+}
@@ -0,0 +1,349 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    spanned::Spanned,
+    Attribute, Error, FnArg, Ident, LitStr, Pat, Result, Signature, Token, Type,
+};
+
+/// The role of a method argument, declared with a parameter attribute.
+#[derive(Clone, Debug)]
+pub enum Param {
+    /// `#[path]` or `#[path("name")]`, replaces the `{name}` placeholder in the path.
+    Path { name: LitStr, ident: Ident },
+    /// `#[query]`, `#[query("name")]` or `#[query("name", style = "csv")]`,
+    /// appends the `name=value` pairs to the query string.
+    Query {
+        name: LitStr,
+        ident: Ident,
+        style: Ident,
+    },
+    /// `#[header]` or `#[header("name")]`, adds a header to the request.
+    Header { name: LitStr, ident: Ident },
+    /// `#[body]`, `#[body(json)]`, `#[body(form)]`, `#[body(raw)]`, `#[body(upload)]` or `#[body("media/type")]`,
+    /// sets the request body, JSON or a media type is encoded with the converter of the service.
+    Body {
+        kind: Ident,
+        ident: Ident,
+        media_type: Option<LitStr>,
+    },
+    /// `#[part]`, `#[part("name")]` or `#[part("name", file)]`, adds a part to the multipart form,
+    /// the `text`, `bytes`, `file` or `json` kind defaults to the one of the argument type.
+    Part {
+        name: LitStr,
+        ident: Ident,
+        kind: Ident,
+    },
+}
+
+impl Param {
+    /// Extracts and strips the parameter attributes from the method signature.
+    pub fn extract(sig: &mut Signature) -> Result<Vec<Self>> {
+        let mut params = vec![];
+
+        for input in sig.inputs.iter_mut() {
+            let arg = match input {
+                FnArg::Typed(arg) => arg,
+                FnArg::Receiver(_) => continue,
+            };
+
+            let (attrs, others) = arg.attrs.drain(..).partition::<Vec<_>, _>(|attr| {
+                ["path", "query", "header", "body", "part"]
+                    .iter()
+                    .any(|name| attr.path.is_ident(name))
+            });
+
+            arg.attrs = others;
+
+            if attrs.is_empty() {
+                continue;
+            }
+
+            let ident = match *arg.pat {
+                Pat::Ident(ref pat) => pat.ident.clone(),
+                ref pat => {
+                    return Err(Error::new(
+                        pat.span(),
+                        "expected an identifier for the annotated argument",
+                    ))
+                }
+            };
+
+            if let Some(attr) = attrs.get(1) {
+                return Err(Error::new(
+                    attr.span(),
+                    format!("`{}` has more than one parameter attribute", ident),
+                ));
+            }
+
+            params.push(Param::parse(&attrs[0], ident, &arg.ty)?);
+        }
+
+        if let Some(Param::Body { ident, .. }) = params
+            .iter()
+            .filter(|param| matches!(param, Param::Body { .. }))
+            .nth(1)
+        {
+            return Err(Error::new(
+                ident.span(),
+                "only one argument could be the request body",
+            ));
+        }
+
+        if params
+            .iter()
+            .any(|param| matches!(param, Param::Part { .. }))
+        {
+            if let Some(Param::Body { ident, .. }) = params
+                .iter()
+                .find(|param| matches!(param, Param::Body { .. }))
+            {
+                return Err(Error::new(
+                    ident.span(),
+                    "the request body could not be combined with `#[part]` arguments",
+                ));
+            }
+        }
+
+        Ok(params)
+    }
+
+    fn parse(attr: &Attribute, ident: Ident, ty: &Type) -> Result<Self> {
+        let name = || -> Result<LitStr> {
+            if attr.tokens.is_empty() {
+                Ok(LitStr::new(&ident.to_string(), ident.span()))
+            } else {
+                attr.parse_args()
+            }
+        };
+
+        if attr.path.is_ident("path") {
+            Ok(Param::Path {
+                name: name()?,
+                ident,
+            })
+        } else if attr.path.is_ident("query") {
+            let QueryArgs { name, style } = if attr.tokens.is_empty() {
+                QueryArgs::default()
+            } else {
+                attr.parse_args()?
+            };
+            let name = name.unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+            let style = match style {
+                None => Ident::new("Multi", ident.span()),
+                Some(style) if style.value() == "multi" => Ident::new("Multi", style.span()),
+                Some(style) if style.value() == "csv" => Ident::new("Csv", style.span()),
+                Some(style) => {
+                    return Err(Error::new(style.span(), "expected `multi` or `csv` style"))
+                }
+            };
+
+            Ok(Param::Query { name, ident, style })
+        } else if attr.path.is_ident("header") {
+            let name = if attr.tokens.is_empty() {
+                LitStr::new(&ident.to_string().replace('_', "-"), ident.span())
+            } else {
+                attr.parse_args()?
+            };
+
+            Ok(Param::Header { name, ident })
+        } else if attr.path.is_ident("part") {
+            let PartArgs { name, kind } = if attr.tokens.is_empty() {
+                PartArgs::default()
+            } else {
+                attr.parse_args()?
+            };
+            let name = name.unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+            let kind = match kind {
+                Some(kind)
+                    if ["text", "bytes", "file", "json"]
+                        .iter()
+                        .any(|name| kind == name) =>
+                {
+                    kind
+                }
+                Some(kind) => {
+                    return Err(Error::new(
+                        kind.span(),
+                        "expected `text`, `bytes`, `file` or `json` part",
+                    ))
+                }
+                None => Ident::new(part_kind(ty), ident.span()),
+            };
+
+            Ok(Param::Part { name, ident, kind })
+        } else {
+            let (kind, media_type) = if attr.tokens.is_empty() {
+                (Ident::new("json", attr.span()), None)
+            } else {
+                attr.parse_args_with(|input: ParseStream| {
+                    if input.peek(LitStr) {
+                        let media_type: LitStr = input.parse()?;
+
+                        Ok((Ident::new("convert", media_type.span()), Some(media_type)))
+                    } else {
+                        Ok((input.parse()?, None))
+                    }
+                })?
+            };
+
+            if media_type.is_none()
+                && !["json", "form", "raw", "upload"]
+                    .iter()
+                    .any(|name| kind == name)
+            {
+                return Err(Error::new(
+                    kind.span(),
+                    "expected `json`, `form`, `raw` or `upload` body, or a media type",
+                ));
+            }
+
+            Ok(Param::Body {
+                kind,
+                ident,
+                media_type,
+            })
+        }
+    }
+
+    /// Returns the argument of the parameter.
+    pub fn ident(&self) -> &Ident {
+        match self {
+            Param::Path { ident, .. }
+            | Param::Query { ident, .. }
+            | Param::Header { ident, .. }
+            | Param::Body { ident, .. }
+            | Param::Part { ident, .. } => ident,
+        }
+    }
+}
+
+impl ToTokens for Param {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let expanded = match self {
+            Param::Path { .. } | Param::Part { .. } => return,
+            Param::Query { name, ident, style } => quote! {
+                .query(&retrofit::query::Query::new(#name, &#ident, retrofit::query::Style::#style))
+            },
+            Param::Header { name, ident } => quote! { .header(#name, &#ident.to_string()) },
+            Param::Body {
+                media_type: Some(media_type),
+                ..
+            } => quote! {
+                .header(retrofit::http::header::CONTENT_TYPE, #media_type)
+                .body(body)
+            },
+            Param::Body { kind, .. } if kind == "json" => quote! {
+                .header(retrofit::http::header::CONTENT_TYPE, retrofit::convert::APPLICATION_JSON)
+                .body(body)
+            },
+            Param::Body { kind, ident, .. } if kind == "raw" => quote! { .body(#ident) },
+            Param::Body { kind, ident, .. } if kind == "upload" => quote! { .upload(#ident) },
+            Param::Body { kind, ident, .. } => quote! { .#kind(&#ident) },
+        };
+
+        expanded.to_tokens(tokens);
+    }
+}
+
+/// Returns the kind of the part from the argument type, e.g. `file` for a `&Path`,
+/// the types which are not text, bytes or paths are serialized as JSON.
+fn part_kind(ty: &Type) -> &'static str {
+    match ty {
+        Type::Reference(reference) => part_kind(&reference.elem),
+        Type::Slice(slice) if matches!(*slice.elem, Type::Path(ref elem) if elem.path.is_ident("u8")) => {
+            "bytes"
+        }
+        Type::Path(path) => {
+            let segment = match path.path.segments.last() {
+                Some(segment) => segment,
+                None => return "json",
+            };
+
+            match segment.arguments {
+                syn::PathArguments::None if segment.ident == "str" || segment.ident == "String" => {
+                    "text"
+                }
+                syn::PathArguments::None if segment.ident == "Bytes" => "bytes",
+                syn::PathArguments::None
+                    if segment.ident == "Path" || segment.ident == "PathBuf" =>
+                {
+                    "file"
+                }
+                syn::PathArguments::AngleBracketed(ref args)
+                    if segment.ident == "Vec"
+                        && matches!(
+                            args.args.first(),
+                            Some(syn::GenericArgument::Type(Type::Path(elem))) if elem.path.is_ident("u8")
+                        ) =>
+                {
+                    "bytes"
+                }
+                _ => "json",
+            }
+        }
+        _ => "json",
+    }
+}
+
+/// The arguments of `#[part(...)]`, e.g. `("avatar", file)`.
+#[derive(Default)]
+struct PartArgs {
+    name: Option<LitStr>,
+    kind: Option<Ident>,
+}
+
+impl Parse for PartArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut args = PartArgs::default();
+
+        if input.peek(LitStr) {
+            args.name = Some(input.parse()?);
+
+            if !input.is_empty() {
+                let _: Token![,] = input.parse()?;
+            }
+        }
+
+        if !input.is_empty() {
+            args.kind = Some(input.parse()?);
+        }
+
+        Ok(args)
+    }
+}
+
+/// The arguments of `#[query(...)]`, e.g. `("ids", style = "csv")`.
+#[derive(Default)]
+struct QueryArgs {
+    name: Option<LitStr>,
+    style: Option<LitStr>,
+}
+
+impl Parse for QueryArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut args = QueryArgs::default();
+
+        if input.peek(LitStr) {
+            args.name = Some(input.parse()?);
+
+            if !input.is_empty() {
+                let _: Token![,] = input.parse()?;
+            }
+        }
+
+        if !input.is_empty() {
+            let ident: Ident = input.parse()?;
+
+            if ident != "style" {
+                return Err(Error::new(ident.span(), "expected `style`"));
+            }
+
+            let _: Token![=] = input.parse()?;
+
+            args.style = Some(input.parse()?);
+        }
+
+        Ok(args)
+    }
+}
@@ -1,20 +1,25 @@
-#![allow(clippy::eval_order_dependence)]
+#![allow(clippy::mixed_read_write_in_expression)]
 
+use std::ops::{Deref, Range};
 use std::result::Result as StdResult;
 
 use lazy_static::lazy_static;
-use proc_macro2::{Span, TokenStream};
-use quote::{quote, ToTokens};
-use regex::Regex;
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use regex::{Captures, Regex};
 use syn::{
+    ext::IdentExt,
     parenthesized,
-    parse::{Parse, ParseStream},
+    parse::{Parse, ParseStream, Parser},
     parse_quote,
     punctuated::Punctuated,
     spanned::Spanned,
-    token, Attribute, Error, Expr, Ident, LitByteStr, LitStr, Result, Token, TraitItemMethod,
+    token, Attribute, Error, Expr, FnArg, Ident, LitByteStr, LitStr, Pat, Result, Token,
+    TraitItemMethod,
 };
 
+use crate::param::Param;
+
 pub fn request(_attr: LitStr, item: TraitItemMethod) -> Result<TokenStream> {
     Ok(item.into_token_stream())
 }
@@ -29,6 +34,7 @@ pub fn args(_attr: Args, item: TraitItemMethod) -> Result<TokenStream> {
 
 pub struct Http {
     pub method: Ident,
+    #[allow(dead_code)]
     pub paren_token: token::Paren,
     pub path: LitStr,
 }
@@ -56,81 +62,57 @@ pub struct Request {
     pub method: http::Method,
     pub path: LitStr,
     pub args: Punctuated<Arg, Token![,]>,
+    pub client: TokenStream,
 }
 
 impl Request {
-    pub fn extract(method: &TraitItemMethod) -> Result<Self> {
+    pub fn extract(method: &TraitItemMethod, params: &[Param]) -> Result<Self> {
         let args = Args::extract("args", &method.attrs)?;
 
         for attr in &method.attrs {
-            if attr.path.is_ident("get") || attr.path == parse_quote! { retrofit::get } {
-                return attr.parse_args().map(|path| Request {
-                    method: http::Method::GET,
-                    path,
-                    args,
-                });
+            let (http_method, path) = if attr.path.is_ident("get")
+                || attr.path == parse_quote! { retrofit::get }
+            {
+                (http::Method::GET, attr.parse_args()?)
             } else if attr.path.is_ident("head") || attr.path == parse_quote! { retrofit::head } {
-                return attr.parse_args().map(|path| Request {
-                    method: http::Method::HEAD,
-                    path,
-                    args,
-                });
-            } else if attr.path.is_ident("patch") || attr.path == parse_quote! { retrofit::patch } {
-                return attr.parse_args().map(|path| Request {
-                    method: http::Method::PATCH,
-                    path,
-                    args,
-                });
+                (http::Method::HEAD, attr.parse_args()?)
             } else if attr.path.is_ident("post") || attr.path == parse_quote! { retrofit::post } {
-                return attr.parse_args().map(|path| Request {
-                    method: http::Method::POST,
-                    path,
-                    args,
-                });
+                (http::Method::POST, attr.parse_args()?)
             } else if attr.path.is_ident("put") || attr.path == parse_quote! { retrofit::put } {
-                return attr.parse_args().map(|path| Request {
-                    method: http::Method::PUT,
-                    path,
-                    args,
-                });
+                (http::Method::PUT, attr.parse_args()?)
             } else if attr.path.is_ident("patch") || attr.path == parse_quote! { retrofit::patch } {
-                return attr.parse_args().map(|path| Request {
-                    method: http::Method::PATCH,
-                    path,
-                    args,
-                });
+                (http::Method::PATCH, attr.parse_args()?)
             } else if attr.path.is_ident("delete") || attr.path == parse_quote! { retrofit::delete }
             {
-                return attr.parse_args().map(|path| Request {
-                    method: http::Method::DELETE,
-                    path,
-                    args,
-                });
+                (http::Method::DELETE, attr.parse_args()?)
             } else if attr.path.is_ident("trace") || attr.path == parse_quote! { retrofit::trace } {
-                return attr.parse_args().map(|path| Request {
-                    method: http::Method::TRACE,
-                    path,
-                    args,
-                });
+                (http::Method::TRACE, attr.parse_args()?)
             } else if attr.path.is_ident("options")
                 || attr.path == parse_quote! { retrofit::options }
             {
-                return attr.parse_args().map(|path| Request {
-                    method: http::Method::OPTIONS,
-                    path,
-                    args,
-                });
+                (http::Method::OPTIONS, attr.parse_args()?)
             } else if attr.path.is_ident("http") || attr.path == parse_quote! { retrofit::http } {
                 let req = attr.parse_args::<Http>()?;
 
-                return Ok(Request {
-                    method: req
-                        .method()
+                (
+                    req.method()
                         .map_err(|err| Error::new(method.sig.span(), err))?,
-                    path: req.path,
-                    args,
-                });
-            }
+                    req.path,
+                )
+            } else {
+                continue;
+            };
+
+            let mut request = Request {
+                method: http_method,
+                path,
+                args,
+                client: quote! { self.client() },
+            };
+
+            request.bind(method, params)?;
+
+            return Ok(request);
         }
 
         Err(Error::new(
@@ -141,10 +123,120 @@ impl Request {
             ),
         ))
     }
+
+    /// Returns the names of the placeholders in the path.
+    fn placeholders(&self) -> Vec<String> {
+        RE_FMT_ARG
+            .captures_iter(&self.path.value())
+            .map(|cap| cap["name"].to_string())
+            .collect()
+    }
+
+    /// Returns true if the argument is used by a placeholder in the path.
+    pub fn uses(&self, ident: &Ident) -> bool {
+        RE_FMT_ARG.captures_iter(&self.path.value()).any(|cap| {
+            match self.args.iter().find(|arg| arg.ident == cap["name"]) {
+                Some(Arg {
+                    expr: Some(expr), ..
+                }) => mentions(expr.to_token_stream(), ident),
+                Some(Arg { ident: name, .. }) => name == ident,
+                None => ident == &cap["name"],
+            }
+        })
+    }
+
+    /// Binds the `#[path]` parameters, and verifies every placeholder in the path
+    /// is bound to an argument of the method.
+    fn bind(&mut self, method: &TraitItemMethod, params: &[Param]) -> Result<()> {
+        let placeholders = self.placeholders();
+
+        for param in params {
+            if let Param::Path { name, ident } = param {
+                if !placeholders.contains(&name.value()) {
+                    return Err(Error::new(
+                        name.span(),
+                        format!("`{{{}}}` not found in the path", name.value()),
+                    ));
+                }
+
+                let name = Ident::parse_any
+                    .parse_str(&name.value())
+                    .map(|mut ident| {
+                        ident.set_span(name.span());
+                        ident
+                    })
+                    .map_err(|_| {
+                        Error::new(
+                            name.span(),
+                            format!("`{}` is not a valid argument name", name.value()),
+                        )
+                    })?;
+
+                self.args.push(parse_quote! { #name = #ident });
+            }
+        }
+
+        let inputs = method
+            .sig
+            .inputs
+            .iter()
+            .flat_map(|input| match input {
+                FnArg::Typed(arg) => match *arg.pat {
+                    Pat::Ident(ref pat) => Some(&pat.ident),
+                    _ => None,
+                },
+                FnArg::Receiver(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let path = self.path.value();
+
+        for cap in RE_FMT_ARG.captures_iter(&path) {
+            let name = &cap["name"];
+
+            if !self.args.iter().any(|arg| arg.ident == name)
+                && !inputs.iter().any(|ident| *ident == name)
+            {
+                return Err(Error::new(
+                    self.subspan(cap.get(0).unwrap().range()),
+                    format!(
+                        "`{{{}}}` is not an argument of the `{}` method",
+                        name, method.sig.ident
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the span of the range in the path with a nightly compiler,
+    /// or the whole path on stable where `Span::subspan` is not available.
+    fn subspan(&self, range: Range<usize>) -> Span {
+        let token = self.path.token();
+        let offset = token.to_string().find('"').map_or(0, |pos| pos + 1);
+
+        token
+            .subspan(range.start + offset..range.end + offset)
+            .unwrap_or_else(|| self.path.span())
+    }
+
+    /// Build the request with the given client instead of the service one.
+    pub fn with_client(self, client: TokenStream) -> Self {
+        Request { client, ..self }
+    }
+}
+
+/// Returns true if the tokens mention the identifier.
+pub fn mentions(tokens: TokenStream, ident: &Ident) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(ref id) => id == ident,
+        TokenTree::Group(group) => mentions(group.stream(), ident),
+        _ => false,
+    })
 }
 
 lazy_static! {
-    static ref RE_FMT_ARG: Regex = Regex::new(r"\{(?P<name>\w+)(:[^\}]+)?\}").unwrap();
+    static ref RE_FMT_ARG: Regex = Regex::new(r"\{(?P<name>\w+)(:(?P<spec>[^\}]+))?\}").unwrap();
 }
 
 impl ToTokens for Request {
@@ -172,45 +264,79 @@ impl ToTokens for Request {
                 }
             }
         };
-        let path = &self.path;
-        let args = {
-            let fmt = path.value();
-            let args = &self.args;
-            let args = args.iter().cloned().chain(
-                RE_FMT_ARG
-                    .captures_iter(&fmt)
-                    .flat_map(|cap| {
-                        let name = &cap["name"];
-                        if args.iter().any(|arg| arg.ident == name) {
-                            None
-                        } else {
-                            Some(Ident::new(name, Span::call_site()))
-                        }
-                    })
-                    .map(|id| {
-                        parse_quote! { #id = #id }
-                    }),
-            );
+        let client = &self.client;
 
-            quote! { #(#args),* }
+        let expanded = quote! {
+            #client.request(#http_method, &url)
         };
 
-        let expanded = quote! {{
-            let url = format!(
-                concat!("{base_url}", #path),
-                base_url = self.base_url,
-                #args
-            );
+        expanded.to_tokens(tokens);
+    }
+}
+
+impl Request {
+    /// Returns the `retrofit::Result<String>` of the URL, which the request is sent to as `url`.
+    ///
+    /// The path arguments are percent-encoded, unless declared as `{name:raw}`,
+    /// a value which is not a valid path segment is returned as `Error::Request`.
+    pub fn url(&self) -> TokenStream {
+        let mut args = vec![];
+        let mut segments = vec![];
+        let template = self.path.value();
+        let fmt = RE_FMT_ARG.replace_all(&template, |cap: &Captures| {
+            let name = Ident::new(&cap["name"], self.path.span());
+            let value = self
+                .args
+                .iter()
+                .find(|arg| arg.ident == name)
+                .and_then(|arg| arg.expr.as_ref())
+                .map_or_else(|| quote! { #name }, |expr| quote! { #expr });
+            let segment = format_ident!("__segment{}", segments.len());
+
+            match cap.name("spec").map(|spec| spec.as_str()) {
+                Some("raw") => args.push(value),
+                Some(spec) => {
+                    let fmt = format!("{{:{}}}", spec);
+
+                    segments.push((segment.clone(), quote! { &format_args!(#fmt, #value) }));
+                    args.push(quote! { #segment });
+                }
+                None => {
+                    segments.push((segment.clone(), quote! { &#value }));
+                    args.push(quote! { #segment });
+                }
+            }
 
-            self.client().as_ref().unwrap().request(#http_method, &url)
-        }};
+            "{}"
+        });
+        let path = LitStr::new(&fmt, self.path.span());
+        let url = quote! {
+            Ok::<_, retrofit::Error>(format!(
+                concat!("{}", #path),
+                self.base_url,
+                #(#args),*
+            ))
+        };
 
-        expanded.to_tokens(tokens);
+        segments
+            .into_iter()
+            .rev()
+            .fold(url, |url, (segment, value)| {
+                quote! { retrofit::path::encode(#value).and_then(|#segment| #url) }
+            })
     }
 }
 
 pub struct Args(Punctuated<Arg, Token![,]>);
 
+impl Deref for Args {
+    type Target = Punctuated<Arg, Token![,]>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 impl Parse for Args {
     fn parse(input: ParseStream<'_>) -> Result<Self> {
         Punctuated::parse_terminated(input).map(Args)
@@ -248,22 +374,33 @@ pub struct Arg {
     pub ident: Ident,
     pub eq_token: Option<Token![=]>,
     pub expr: Option<Expr>,
+    /// The nested arguments, e.g. `retry(max = 3)`.
+    pub nested: Option<Punctuated<Arg, Token![,]>>,
 }
 
 impl Parse for Arg {
     fn parse(input: ParseStream<'_>) -> Result<Self> {
-        let ident = input.parse()?;
+        let ident = Ident::parse_any(input)?;
         let lookahead = input.lookahead1();
         let (eq_token, expr) = if lookahead.peek(Token![=]) {
             (Some(input.parse()?), Some(input.parse()?))
         } else {
             (None, None)
         };
+        let nested = if expr.is_none() && input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+
+            Some(Punctuated::parse_terminated(&content)?)
+        } else {
+            None
+        };
 
         Ok(Arg {
             ident,
             eq_token,
             expr,
+            nested,
         })
     }
 }
@@ -273,5 +410,64 @@ impl ToTokens for Arg {
         self.ident.to_tokens(tokens);
         self.eq_token.to_tokens(tokens);
         self.expr.to_tokens(tokens);
+        if let Some(ref nested) = self.nested {
+            quote! { (#nested) }.to_tokens(tokens);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: TraitItemMethod) -> Result<Request> {
+        Request::extract(&method, &[])
+    }
+
+    #[test]
+    fn encode_path_arguments() {
+        let req = request(parse_quote! {
+            #[get("/repos/{owner}/{path:raw}/{id:04}")]
+            fn get(&self, owner: &str, path: &str, id: u32);
+        })
+        .unwrap();
+        let tokens = req.url().to_string();
+
+        assert!(tokens.contains(r#"concat ! ("{}" , "/repos/{}/{}/{}")"#));
+        assert!(tokens.contains("retrofit :: path :: encode (& owner) . and_then (| __segment0 |"));
+        assert!(tokens.contains(
+            r#"retrofit :: path :: encode (& format_args ! ("{:04}" , id)) . and_then (| __segment1 |"#
+        ));
+        assert!(tokens.contains("self . base_url , __segment0 , path , __segment1"));
+    }
+
+    #[test]
+    fn validate_path_names() {
+        let method: TraitItemMethod = parse_quote! {
+            #[get("/items/{1}/{type}")]
+            fn get(&self, id: u32, ty: &str);
+        };
+        let param = |name: &str, ident: &str| Param::Path {
+            name: LitStr::new(name, Span::call_site()),
+            ident: Ident::new(ident, Span::call_site()),
+        };
+
+        let err = Request::extract(&method, &[param("1", "id")])
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "`1` is not a valid argument name");
+
+        let err = Request::extract(&method, &[param("a-b", "id")])
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "`{a-b}` not found in the path");
+
+        let err = Request::extract(&method, &[param("type", "ty")])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "`{1}` is not an argument of the `get` method"
+        );
     }
 }
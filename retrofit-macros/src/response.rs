@@ -1,16 +1,60 @@
 use proc_macro2::TokenStream;
 use quote::ToTokens;
-use syn::{parse_quote, Attribute, Expr, Result, TraitItemMethod};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_quote, Attribute, Expr, Ident, LitStr, Result, Token, TraitItemMethod, Type,
+};
 
-pub fn response(_attr: Expr, item: TraitItemMethod) -> Result<TokenStream> {
+pub fn response(_attr: Response, item: TraitItemMethod) -> Result<TokenStream> {
     Ok(item.into_token_stream())
 }
 
-pub fn extract(attrs: &[Attribute]) -> Result<Option<Expr>> {
+/// The options of the `#[response(...)]` attribute.
+#[derive(Default)]
+pub struct Response {
+    /// The expression to decode the response body, e.g. `text()`.
+    pub decode: Option<Expr>,
+    /// The typed error body of a non-success response, e.g. `error = ApiError`.
+    pub error: Option<Type>,
+    /// The headers returned beside or instead of the body, in the order of the returned tuple,
+    /// e.g. `header = "etag"`.
+    pub headers: Vec<LitStr>,
+}
+
+impl Parse for Response {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut response = Response::default();
+
+        while !input.is_empty() {
+            if input.peek(Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]) {
+                let ident: Ident = input.parse()?;
+
+                let _: Token![=] = input.parse()?;
+
+                if ident == "error" {
+                    response.error = Some(input.parse()?);
+                } else if ident == "header" {
+                    response.headers.push(input.parse()?);
+                } else {
+                    return Err(syn::Error::new(ident.span(), "unknown response option"));
+                }
+            } else {
+                response.decode = Some(input.parse()?);
+            }
+
+            if !input.is_empty() {
+                let _: Token![,] = input.parse()?;
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+pub fn extract(attrs: &[Attribute]) -> Result<Response> {
     let path = parse_quote! { retrofit::response };
     attrs
         .iter()
         .find(|attr| attr.path.is_ident("response") || attr.path == path)
-        .map(|attr| attr.parse_args())
-        .transpose()
+        .map_or_else(|| Ok(Response::default()), |attr| attr.parse_args())
 }
@@ -0,0 +1,113 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{parse_quote, spanned::Spanned, Attribute, Error, Expr, Lit, Result, TraitItemMethod};
+
+use crate::request::{Arg, Args};
+
+pub fn retry(_attr: Args, item: TraitItemMethod) -> Result<TokenStream> {
+    Ok(item.into_token_stream())
+}
+
+/// The retry policy, e.g. `max = 3, backoff = "exponential", on = [502, 503, 504]`.
+#[derive(Clone, Debug)]
+pub struct Retry {
+    max: Option<Expr>,
+    backoff: Option<Expr>,
+    delay_ms: Option<Expr>,
+    on: Option<Expr>,
+    /// Retry the non-idempotent requests, e.g. `POST`.
+    pub non_idempotent: bool,
+    pub span: Span,
+}
+
+impl Retry {
+    /// Extracts the retry policy of the method from `#[retry(...)]`.
+    pub fn extract(attrs: &[Attribute]) -> Result<Option<Self>> {
+        let path = parse_quote! { retrofit::retry };
+
+        attrs
+            .iter()
+            .find(|attr| attr.path.is_ident("retry") || attr.path == path)
+            .map(|attr| {
+                let args = attr.parse_args::<Args>()?;
+
+                Retry::new(args, attr.path.span())
+            })
+            .transpose()
+    }
+
+    pub fn new<I: IntoIterator<Item = Arg>>(args: I, span: Span) -> Result<Self> {
+        let mut retry = Retry {
+            max: None,
+            backoff: None,
+            delay_ms: None,
+            on: None,
+            non_idempotent: false,
+            span,
+        };
+
+        for Arg { ident, expr, .. } in args {
+            match (ident.to_string().as_str(), expr) {
+                ("max", Some(expr)) => retry.max = Some(expr),
+                ("delay_ms", Some(expr)) => retry.delay_ms = Some(expr),
+                ("on", Some(expr)) => retry.on = Some(expr),
+                ("backoff", Some(expr)) => {
+                    let backoff = match expr {
+                        Expr::Lit(syn::ExprLit {
+                            lit: Lit::Str(ref s),
+                            ..
+                        }) => match s.value().as_str() {
+                            "none" => quote! { None },
+                            "constant" => quote! { Constant },
+                            "exponential" => quote! { Exponential },
+                            _ => {
+                                return Err(Error::new(
+                                    s.span(),
+                                    "expected `none`, `constant` or `exponential` backoff",
+                                ))
+                            }
+                        },
+                        expr => {
+                            return Err(Error::new(expr.span(), "expected backoff string"));
+                        }
+                    };
+
+                    retry.backoff = Some(parse_quote! { retrofit::Backoff::#backoff });
+                }
+                ("non_idempotent", None) => retry.non_idempotent = true,
+                _ => {
+                    return Err(Error::new(
+                        ident.span(),
+                        "expected `max`, `backoff`, `delay_ms`, `on` or `non_idempotent`",
+                    ))
+                }
+            }
+        }
+
+        Ok(retry)
+    }
+}
+
+impl ToTokens for Retry {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let max = self
+            .max
+            .as_ref()
+            .map_or_else(|| quote! { 3 }, |max| quote! { #max });
+        let backoff = self
+            .backoff
+            .as_ref()
+            .map(|backoff| quote! { .backoff(#backoff) });
+        let delay = self
+            .delay_ms
+            .as_ref()
+            .map(|ms| quote! { .delay(std::time::Duration::from_millis(#ms)) });
+        let on = self.on.as_ref().map(|on| quote! { .on(&#on) });
+
+        let expanded = quote! {
+            retrofit::Retry::new(#max) #backoff #delay #on
+        };
+
+        expanded.to_tokens(tokens);
+    }
+}
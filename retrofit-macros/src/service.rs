@@ -2,13 +2,17 @@ use std::ops::Deref;
 
 use case::CaseExt;
 use proc_macro2::{Ident, Span, TokenStream};
-use quote::{quote, ToTokens};
-use syn::{parse_quote, punctuated::Punctuated, ItemTrait, Result, Token};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{parse_quote, punctuated::Punctuated, spanned::Spanned, ItemTrait, Result, Token};
 
 use crate::{
+    auth::Auth,
     header::Headers,
-    request::{Arg, Args, Request},
+    paginate::Paginate,
+    param::Param,
+    request::{mentions, Arg, Args, Request},
     response,
+    retry::Retry,
 };
 
 pub fn client(_args: Args, item: ItemTrait) -> Result<TokenStream> {
@@ -16,30 +20,52 @@ pub fn client(_args: Args, item: ItemTrait) -> Result<TokenStream> {
 }
 
 pub fn service(args: Args, mut item: ItemTrait) -> Result<TokenStream> {
-    ensure_trait_bound(&mut item.supertraits);
+    let mode = Mode::extract(&args);
+    let api_error = args
+        .iter()
+        .find(|arg| arg.ident == "error")
+        .and_then(|arg| arg.expr.as_ref())
+        .map(|expr| syn::parse2::<syn::Type>(expr.to_token_stream()))
+        .transpose()?;
+    let service_error = match api_error {
+        Some(ref ty) => quote! { retrofit::Error<#ty> },
+        None => quote! { retrofit::Error },
+    };
 
-    let client_options =
-        Args::extract("client", &item.attrs)?
-            .into_iter()
-            .map(|Arg { ident, expr, .. }| {
-                quote! {
-                    .#ident(#expr)
-                }
-            });
-    let service_options = args.into_iter().flat_map(|Arg { ident, expr, .. }| {
-        expr.map(|expr| {
+    let retry = args
+        .iter()
+        .find(|arg| arg.ident == "retry")
+        .map(|arg| match (&arg.nested, &arg.expr) {
+            (Some(nested), _) => Retry::new(nested.clone(), arg.ident.span()),
+            (None, None) => Retry::new(None, arg.ident.span()),
+            (None, Some(expr)) => Err(syn::Error::new(expr.span(), "expected `retry(...)`")),
+        })
+        .transpose()?;
+
+    ensure_trait_bound(&mut item.supertraits, mode, &service_error);
+
+    let client_options = Args::extract("client", &item.attrs)?
+        .into_iter()
+        .map(|Arg { ident, expr, .. }| {
             quote! {
-                #ident: #expr.into(),
+                .#ident(#expr)
             }
         })
-    });
+        .collect::<Vec<_>>();
+    let base_url = args
+        .iter()
+        .find(|arg| arg.ident == "base_url")
+        .and_then(|arg| arg.expr.as_ref())
+        .map_or_else(|| quote! { String::new() }, |expr| quote! { #expr.into() });
 
     let vis = &item.vis;
     let trait_name = &item.ident;
     let fn_name = Ident::new(&trait_name.to_string().to_snake(), Span::call_site());
     let client_name = Ident::new(&format!("{}Client", trait_name), Span::call_site());
+    let builder_name = Ident::new(&format!("{}Builder", trait_name), Span::call_site());
 
-    let methods = generate_methods(&mut item.items);
+    let methods = generate_methods(&mut item.items, mode, api_error.as_ref(), retry.as_ref())
+        .collect::<Vec<_>>();
 
     let default_headers = Headers::extract("default_headers", &item.attrs)?;
     let default_headers = if default_headers.is_empty() {
@@ -47,75 +73,337 @@ pub fn service(args: Args, mut item: ItemTrait) -> Result<TokenStream> {
     } else {
         Some(quote! { .default_headers(#default_headers) })
     };
+    let auth = Auth::extract(&item.attrs)?;
+
+    let backend = mode.backend();
+    let async_trait = mode.async_trait();
+    let has_calls = methods.iter().any(|method| method.call);
+    let mut other_builder = None;
+    let (transport_field, transport_init, transport_impl) = if has_calls {
+        let (field, other_backend, flavor) = match mode {
+            Mode::Blocking => (quote! { async_client }, Mode::Async.backend(), "async"),
+            Mode::Async => (
+                quote! { blocking_client },
+                Mode::Blocking.backend(),
+                "blocking",
+            ),
+        };
+        let field_builder = format_ident!("{}_builder", field.to_string());
+        let other_client = quote! {
+            self.#field.get_or_init(|| {
+                let mut builder = self.#field_builder.lock().unwrap().take().unwrap_or_else(#other_backend::Client::builder)
+                    .user_agent(APP_USER_AGENT)
+                    #default_headers
+                    #(#client_options)*;
 
+                tracing::trace!(?builder);
+
+                builder.build().expect("client")
+            }).clone()
+        };
+        let (client, blocking_client) = match mode {
+            Mode::Blocking => (other_client, quote! { #client_name::client(self).clone() }),
+            Mode::Async => (quote! { #client_name::client(self).clone() }, other_client),
+        };
+
+        let builder_doc = format!(
+            "Use a preconfigured `ClientBuilder` of the {} client which sends a `Call<T>`,\n\
+             the `User-Agent`, default headers and client options are applied on it.",
+            flavor
+        );
+        let client_doc = format!(
+            "Use a preconfigured `Client` of the {} client which sends a `Call<T>` as is.",
+            flavor
+        );
+        let warning = format!(
+            "the {} client of `Call<T>` is not configured by `client_builder` or `client`, \
+             use `{}` or `{}`",
+            flavor, field_builder, field
+        );
+
+        other_builder = Some((
+            quote! {
+                #field_builder: Option<#other_backend::ClientBuilder>,
+                #field: Option<#other_backend::Client>,
+            },
+            quote! {
+                #field_builder: None,
+                #field: None,
+            },
+            quote! {
+                #[doc = #builder_doc]
+                pub fn #field_builder(mut self, builder: #other_backend::ClientBuilder) -> Self {
+                    self.#field_builder = Some(builder);
+                    self
+                }
+
+                #[doc = #client_doc]
+                pub fn #field(mut self, client: #other_backend::Client) -> Self {
+                    self.#field = Some(client);
+                    self
+                }
+            },
+            quote! {
+                if (self.builder.is_some() || self.client.is_some())
+                    && self.#field_builder.is_none()
+                    && self.#field.is_none()
+                {
+                    tracing::warn!(#warning);
+                }
+            },
+        ));
+
+        (
+            Some(quote! {
+                #field_builder: std::sync::Arc<std::sync::Mutex<Option<#other_backend::ClientBuilder>>>,
+                #field: std::sync::Arc<retrofit::OnceCell<#other_backend::Client>>,
+            }),
+            Some(quote! {
+                #field_builder: std::sync::Arc::new(std::sync::Mutex::new(self.#field_builder)),
+                #field: std::sync::Arc::new(self.#field.map_or_else(retrofit::OnceCell::new, retrofit::OnceCell::from)),
+            }),
+            Some(quote! {
+                impl retrofit::Transport for #client_name {
+                    fn client(&self) -> retrofit::Client {
+                        #client
+                    }
+
+                    fn blocking_client(&self) -> retrofit::blocking::Client {
+                        #blocking_client
+                    }
+
+                    fn interceptors(&self) -> &[std::sync::Arc<dyn retrofit::Interceptor>] {
+                        &self.interceptors
+                    }
+
+                    fn credentials(&self) -> Option<&dyn retrofit::CredentialProvider> {
+                        self.credentials.as_deref()
+                    }
+
+                    fn converters(&self) -> &retrofit::Converters {
+                        &self.converters
+                    }
+                }
+            }),
+        )
+    } else {
+        (None, None, None)
+    };
+    let (other_builder_field, other_builder_default, other_builder_methods, other_builder_check) =
+        match other_builder {
+            Some((field, default, methods, check)) => {
+                (Some(field), Some(default), Some(methods), Some(check))
+            }
+            None => (None, None, None, None),
+        };
     let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
-    let impl_fn = quote! {
-        #vis fn #fn_name() -> impl #trait_name {
-            struct #client_name {
-                builder: std::cell::RefCell<Option<retrofit::blocking::ClientBuilder>>,
-                client: std::cell::RefCell<Option<retrofit::blocking::Client>>,
-                init: std::sync::Once,
-                base_url: String,
+    let client_impl = quote! {
+        impl #client_name {
+            fn client(&self) -> &#backend::Client {
+                self.client.get_or_init(|| {
+                    let mut builder = self.builder.lock().unwrap().take().unwrap_or_else(#backend::Client::builder)
+                        .user_agent(APP_USER_AGENT)
+                        #default_headers
+                        #(#client_options)*;
+
+                    tracing::trace!(?builder);
+
+                    builder.build().expect("client")
+                })
+            }
+        }
+
+        impl #builder_name {
+            /// Build the service client.
+            pub fn build(self) -> #client_name {
+                #other_builder_check
+
+                #client_name {
+                    builder: std::sync::Arc::new(std::sync::Mutex::new(self.builder)),
+                    client: std::sync::Arc::new(self.client.map_or_else(retrofit::OnceCell::new, retrofit::OnceCell::from)),
+                    #transport_init
+                    base_url: self.base_url,
+                    interceptors: self.interceptors,
+                    credentials: self.credentials,
+                    converters: self.converters,
+                }
             }
+        }
+    };
+    let builder_doc = format!("A builder to construct a [`{}`].", client_name);
+    let client_doc = format!("The client of the [`{}`] service.", trait_name);
+    let impl_client = quote! {
+        #[doc = #client_doc]
+        #[derive(Clone)]
+        #vis struct #client_name {
+            builder: std::sync::Arc<std::sync::Mutex<Option<#backend::ClientBuilder>>>,
+            client: std::sync::Arc<retrofit::OnceCell<#backend::Client>>,
+            #transport_field
+            base_url: String,
+            interceptors: Vec<std::sync::Arc<dyn retrofit::Interceptor>>,
+            credentials: Option<std::sync::Arc<dyn retrofit::CredentialProvider>>,
+            converters: retrofit::Converters,
+        }
+
+        #[doc = #builder_doc]
+        #vis struct #builder_name {
+            base_url: String,
+            builder: Option<#backend::ClientBuilder>,
+            client: Option<#backend::Client>,
+            #other_builder_field
+            interceptors: Vec<std::sync::Arc<dyn retrofit::Interceptor>>,
+            credentials: Option<std::sync::Arc<dyn retrofit::CredentialProvider>>,
+            converters: retrofit::Converters,
+        }
+
+        const _: () = {
+            #[allow(unused_imports)]
+            use retrofit::backend::prelude::*;
 
             impl retrofit::Service for #client_name {
-                type Error = retrofit::Error;
-                type Body = retrofit::blocking::Body;
-                type Form = retrofit::blocking::multipart::Form;
+                type Error = #service_error;
+                type Body = #backend::Body;
+                type Form = #backend::multipart::Form;
+                type Upload = #backend::Upload;
             }
 
-            impl #impl_generics #trait_name for #client_name #ty_generics #where_clause {
+            #async_trait
+            impl #impl_generics #trait_name #ty_generics for #client_name #where_clause {
                 #(#methods)*
             }
 
             static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
 
+            #transport_impl
+
             impl #client_name {
-                fn with_builder(mut self, builder: retrofit::blocking::ClientBuilder) -> Self {
-                    *self.builder.borrow_mut() = Some(builder);
+                /// Returns a builder to construct the service client.
+                pub fn builder() -> #builder_name {
+                    #builder_name::default()
+                }
+            }
+
+            impl Default for #builder_name {
+                fn default() -> Self {
+                    #builder_name {
+                        base_url: #base_url,
+                        builder: None,
+                        client: None,
+                        #other_builder_default
+                        interceptors: Vec::new(),
+                        credentials: None,
+                        converters: Default::default(),
+                    }
+                }
+            }
+
+            impl #builder_name {
+                /// Sets the base URL of every request.
+                pub fn base_url<S: Into<String>>(mut self, base_url: S) -> Self {
+                    self.base_url = base_url.into();
                     self
                 }
 
-                fn with_client(mut self, client: retrofit::blocking::Client) -> Self {
-                    *self.client.borrow_mut() = Some(client);
+                /// Use a preconfigured `ClientBuilder`,
+                /// the `User-Agent`, default headers and client options are applied on it.
+                pub fn client_builder(mut self, builder: #backend::ClientBuilder) -> Self {
+                    self.builder = Some(builder);
                     self
                 }
 
-                fn client(&self) -> std::cell::Ref<Option<retrofit::blocking::Client>> {
-                    self.init.call_once(|| {
-                        if self.client.borrow().is_none() {
-                            let mut builder = self.builder.borrow_mut().take().unwrap_or_else(retrofit::blocking::Client::builder)
-                                .user_agent(APP_USER_AGENT)
-                                #default_headers
-                                #(#client_options)*;
+                /// Use a preconfigured `Client` as is.
+                pub fn client(mut self, client: #backend::Client) -> Self {
+                    self.client = Some(client);
+                    self
+                }
 
-                            tracing::trace!(?builder);
+                #other_builder_methods
 
-                            *self.client.borrow_mut() = Some(builder.build().expect("client"))
-                        }
-                    });
-                    self.client.borrow()
+                /// Adds an interceptor of every request and response,
+                /// the interceptors are executed in the order they are added.
+                pub fn interceptor<I: retrofit::Interceptor + 'static>(mut self, interceptor: I) -> Self {
+                    self.interceptors.push(std::sync::Arc::new(interceptor));
+                    self
+                }
+
+                /// Adds a converter of the request and response bodies of its media type,
+                /// which takes precedence over the converters added before and the built-in JSON converter.
+                pub fn converter<C: retrofit::Converter + 'static>(mut self, converter: C) -> Self {
+                    self.converters.register(converter);
+                    self
                 }
-            }
 
-            #client_name {
-                builder: std::cell::RefCell::new(None),
-                client: std::cell::RefCell::new(None),
-                init: std::sync::Once::new(),
-                #(#service_options)*
+                #auth
             }
+
+            #client_impl
+        };
+
+        #vis fn #fn_name() -> #client_name {
+            #client_name::builder().build()
         }
     };
 
+    if let Some(async_trait) = async_trait {
+        item.attrs.push(async_trait);
+    }
+
     let expanded = quote! {
         #item
-        #impl_fn
+        #impl_client
     };
 
     Ok(expanded)
 }
 
-fn ensure_trait_bound(supertraits: &mut Punctuated<syn::TypeParamBound, Token![+]>) {
+/// The flavor of the generated client.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    /// Send requests with the blocking client.
+    Blocking,
+    /// Send requests with the async client, every method becomes an `async fn`.
+    Async,
+}
+
+impl Mode {
+    fn extract(args: &Args) -> Self {
+        if args
+            .iter()
+            .any(|arg| arg.ident == "async" && arg.expr.is_none())
+        {
+            Mode::Async
+        } else {
+            Mode::Blocking
+        }
+    }
+
+    fn backend(self) -> TokenStream {
+        match self {
+            Mode::Blocking => quote! { retrofit::blocking },
+            Mode::Async => quote! { retrofit },
+        }
+    }
+
+    fn async_trait(self) -> Option<syn::Attribute> {
+        match self {
+            Mode::Blocking => None,
+            Mode::Async => Some(parse_quote! { #[retrofit::async_trait] }),
+        }
+    }
+
+    fn await_token(self) -> Option<TokenStream> {
+        match self {
+            Mode::Blocking => None,
+            Mode::Async => Some(quote! { .await }),
+        }
+    }
+}
+
+fn ensure_trait_bound(
+    supertraits: &mut Punctuated<syn::TypeParamBound, Token![+]>,
+    mode: Mode,
+    error: &TokenStream,
+) {
     let bounded = supertraits.iter().any(|t| match t {
         syn::TypeParamBound::Trait(syn::TraitBound { path, .. }) => {
             path.is_ident("Service") || *path == parse_quote! { retrofit::Service }
@@ -124,54 +412,564 @@ fn ensure_trait_bound(supertraits: &mut Punctuated<syn::TypeParamBound, Token![+
     });
 
     if !bounded {
+        let backend = mode.backend();
+
         supertraits.push(syn::TypeParamBound::Trait(parse_quote! {
             retrofit::Service<
-                Error = retrofit::Error,
-                Body = retrofit::blocking::Body,
-                Form = retrofit::blocking::multipart::Form,
+                Error = #error,
+                Body = #backend::Body,
+                Form = #backend::multipart::Form,
+                Upload = #backend::Upload,
             >
         }));
     }
 }
 
-fn generate_methods<'a>(items: &'a mut [syn::TraitItem]) -> impl Iterator<Item = Method> + 'a {
+fn generate_methods<'a>(
+    items: &'a mut [syn::TraitItem],
+    mode: Mode,
+    service_error: Option<&'a syn::Type>,
+    service_retry: Option<&'a Retry>,
+) -> impl Iterator<Item = Method<'a>> + 'a {
     items
         .iter_mut()
         .flat_map(|item| match item {
             syn::TraitItem::Method(method) if method.default.is_none() => Some(method),
             _ => None,
         })
-        .map(|method| {
+        .map(move |method| {
+            let mut call = false;
+            let mut paginated = None;
+            let mut metadata = false;
+            let mut download = false;
+            let mut ndjson = None;
+            let mut output = None;
+            let params = Param::extract(&mut method.sig);
+            let paginate = Paginate::extract(&method.attrs);
+            let retry = Retry::extract(&method.attrs);
+            let response = response::extract(&method.attrs);
+            let method_error = response
+                .as_ref()
+                .ok()
+                .and_then(|response| response.error.as_ref());
+            let api_error = method_error.or(service_error).cloned();
+            let error = match method_error {
+                Some(ty) => quote! { retrofit::Error<#ty> },
+                None => quote! { Self::Error },
+            };
+
             match method.sig.output {
                 syn::ReturnType::Default => {
-                    method.sig.output = parse_quote! { -> Result<(), Self::Error> };
+                    method.sig.output = parse_quote! { -> Result<(), #error> };
                 }
-                syn::ReturnType::Type(_, ref mut ty) => {
-                    let return_result = match ty.as_ref() {
-                        syn::Type::Path(syn::TypePath { path, .. }) if path.is_ident("Result") => {
-                            true
+                syn::ReturnType::Type(_, ref mut ty) if wrapped(ty, "Call").is_some() => {
+                    let mut return_type = wrapped(ty, "Call").cloned().expect("call");
+                    output = Some(return_type.clone());
+                    if let Some(body) = wrapped(&return_type, "Response").cloned() {
+                        metadata = true;
+                        return_type = parse_quote! { retrofit::Response<#body> };
+                    }
+                    **ty = match api_error {
+                        Some(ref api_error) => {
+                            parse_quote! { retrofit::DeferredCall<'_, #return_type, #api_error> }
                         }
-                        _ => false,
+                        None => parse_quote! { retrofit::DeferredCall<'_, #return_type> },
                     };
+                    call = true;
+                }
+                syn::ReturnType::Type(_, ref mut ty) if wrapped(ty, "Paginate").is_some() => {
+                    let return_type = wrapped(ty, "Paginate");
+                    let backend = mode.backend();
+                    paginated = Some(ty.span());
+                    **ty = match api_error {
+                        Some(ref api_error) => {
+                            parse_quote! { #backend::Paginate<#return_type, #api_error> }
+                        }
+                        None => parse_quote! { #backend::Paginate<#return_type> },
+                    };
+                }
+                syn::ReturnType::Type(_, ref mut ty) if wrapped(ty, "Ndjson").is_some() => {
+                    let item = wrapped(ty, "Ndjson");
+                    let backend = mode.backend();
+                    ndjson = Some(ty.span());
+                    **ty = parse_quote! { Result<#backend::Ndjson<#item>, #error> };
+                }
+                syn::ReturnType::Type(_, ref mut ty) if is_download(ty) => {
+                    let backend = mode.backend();
+                    download = true;
+                    **ty = parse_quote! { Result<#backend::Download, #error> };
+                }
+                syn::ReturnType::Type(_, ref mut ty) => {
+                    let return_result = matches!(
+                        ty.as_ref(),
+                        syn::Type::Path(syn::TypePath { path, .. }) if path.is_ident("Result")
+                    );
 
-                    if !return_result {
+                    if let Some(body) = wrapped(ty, "Response") {
+                        metadata = true;
+                        **ty = parse_quote! { Result<retrofit::Response<#body>, #error> }
+                    } else if !return_result {
                         let return_type = ty.as_ref();
-                        *ty = Box::new(parse_quote! { Result<#return_type, Self::Error> })
+                        output = Some(return_type.clone());
+                        **ty = parse_quote! { Result<#return_type, #error> }
                     }
                 }
             }
 
-            Method(method)
+            if mode == Mode::Async && !call && paginated.is_none() && method.sig.asyncness.is_none()
+            {
+                method.sig.asyncness = Some(Default::default());
+            }
+
+            Method {
+                method,
+                mode,
+                call,
+                paginated,
+                metadata,
+                download,
+                ndjson,
+                output,
+                params,
+                paginate,
+                response,
+                api_error: api_error.is_some(),
+                retry,
+                service_retry,
+            }
         })
 }
 
-struct Method<'a>(&'a syn::TraitItemMethod);
+/// Returns true if the method was declared to return a `Download`.
+fn is_download(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => {
+            path.is_ident("Download")
+                || (path.segments.len() == 2
+                    && path.segments[0].ident == "retrofit"
+                    && path.segments[1].ident == "Download"
+                    && path.segments[1].arguments.is_empty())
+        }
+        _ => false,
+    }
+}
+
+/// Returns `T` if the method was declared to return a `Call<T>` or `Paginate<T>`.
+fn wrapped<'a>(ty: &'a syn::Type, name: &str) -> Option<&'a syn::Type> {
+    match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path })
+            if path.segments.len() == 1
+                || (path.segments.len() == 2 && path.segments[0].ident == "retrofit") =>
+        {
+            let segment = path.segments.last()?;
+
+            match segment.arguments {
+                syn::PathArguments::AngleBracketed(ref args)
+                    if segment.ident == name && args.args.len() == 1 =>
+                {
+                    match args.args.first() {
+                        Some(syn::GenericArgument::Type(ty)) => Some(ty),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns the media type of a serde format, declared with `#[request(name = value)]`
+/// and `#[response(name())]`, which is converted by the converter of the media type.
+fn serde_format(ident: &Ident) -> Option<TokenStream> {
+    let media_type = match ident.to_string().as_str() {
+        "xml" => quote! { APPLICATION_XML },
+        "msgpack" => quote! { APPLICATION_MSGPACK },
+        "cbor" => quote! { APPLICATION_CBOR },
+        _ => return None,
+    };
+
+    Some(quote! { retrofit::convert::#media_type })
+}
+
+/// Returns the media type of a `#[request(...)]` body encoded with the converter of the service.
+fn encode_format(ident: &Ident) -> Option<TokenStream> {
+    if ident == "json" {
+        Some(quote! { retrofit::convert::APPLICATION_JSON })
+    } else {
+        serde_format(ident)
+    }
+}
+
+/// The decoding of the response body.
+enum Decode {
+    /// A `#[response(...)]` decoder of the backend response, e.g. `text()`.
+    Backend(TokenStream),
+    /// `#[response(xml())]` or `#[response(xml("charset"))]`, with the default encoding of the text.
+    Xml(TokenStream),
+    /// `#[response(msgpack())]` or `#[response(cbor())]`, with the converter of the media type.
+    Format(TokenStream),
+    /// `#[response(protobuf())]`, a `prost::Message` accepted as `application/x-protobuf`.
+    Protobuf,
+    /// `#[response(ndjson())]`, the lines of the body decoded as JSON as they are received.
+    Ndjson,
+    /// The converter of the response `Content-Type`.
+    Convert,
+}
+
+impl Decode {
+    fn new(decode: &syn::Expr) -> Result<Self> {
+        let call = match decode {
+            syn::Expr::Call(call) => call,
+            _ => return Ok(Decode::Backend(quote! { #decode })),
+        };
+        let name = match *call.func {
+            syn::Expr::Path(ref func) => func.path.get_ident(),
+            _ => None,
+        };
+        let no_args = |name: &Ident, decode: Decode| {
+            if call.args.is_empty() {
+                Ok(decode)
+            } else {
+                Err(syn::Error::new(
+                    call.args.span(),
+                    format!("`{}()` takes no arguments", name),
+                ))
+            }
+        };
+
+        match name {
+            Some(name) if name == "xml" => match call.args.len() {
+                0 => Ok(Decode::Xml(quote! { "utf-8" })),
+                1 => {
+                    let encoding = &call.args[0];
+
+                    Ok(Decode::Xml(quote! { #encoding }))
+                }
+                _ => Err(syn::Error::new(
+                    call.args.span(),
+                    "expected the default encoding of the XML text",
+                )),
+            },
+            Some(name) if name == "protobuf" => no_args(name, Decode::Protobuf),
+            Some(name) if name == "ndjson" => no_args(name, Decode::Ndjson),
+            Some(name) => match serde_format(name) {
+                Some(media_type) => no_args(name, Decode::Format(media_type)),
+                None => Ok(Decode::Backend(quote! { #decode })),
+            },
+            None => Ok(Decode::Backend(quote! { #decode })),
+        }
+    }
+}
+
+/// The body returned beside the `#[response(header = "...")]` values.
+#[derive(Clone, Copy, PartialEq)]
+enum Body {
+    /// The decoded body is the first element of the tuple.
+    Decoded,
+    /// The body is not returned, `()` as the first element of the tuple, or a single header.
+    Unit,
+}
+
+struct Method<'a> {
+    method: &'a syn::TraitItemMethod,
+    mode: Mode,
+    call: bool,
+    /// The span of the `Paginate<T>` return type.
+    paginated: Option<Span>,
+    /// The method was declared to return a `Response<T>` with the status, headers and URL.
+    metadata: bool,
+    /// The method was declared to return a `Download` of the streamed body.
+    download: bool,
+    /// The span of the `Ndjson<T>` return type.
+    ndjson: Option<Span>,
+    /// The type of the value declared by the method, before it is wrapped in a `Result` or `DeferredCall`.
+    output: Option<syn::Type>,
+    params: Result<Vec<Param>>,
+    paginate: Result<Option<(Paginate, Span)>>,
+    response: Result<response::Response>,
+    /// Decode the body of a non-success response as the typed error body.
+    api_error: bool,
+    retry: Result<Option<Retry>>,
+    service_retry: Option<&'a Retry>,
+}
 
 impl<'a> Deref for Method<'a> {
     type Target = syn::TraitItemMethod;
 
     fn deref(&self) -> &Self::Target {
-        self.0
+        self.method
+    }
+}
+
+impl<'a> Method<'a> {
+    /// Implements the method with the compile error, so the error is not buried by a missing method.
+    fn error(&self, err: &syn::Error) -> TokenStream {
+        let sig = &self.sig;
+        let err = err.to_compile_error();
+
+        quote! {
+            #sig { #err }
+        }
+    }
+
+    /// Returns the retry policy of the method, or the service default for an idempotent request.
+    fn retry_policy(&self, request: &Request) -> Result<Option<&Retry>> {
+        let idempotent = matches!(
+            request.method,
+            http::Method::GET
+                | http::Method::HEAD
+                | http::Method::PUT
+                | http::Method::DELETE
+                | http::Method::OPTIONS
+        );
+
+        match self.retry {
+            Ok(Some(ref retry)) if !idempotent && !retry.non_idempotent => Err(syn::Error::new(
+                retry.span,
+                format!(
+                    "`{}` request is not idempotent, use `non_idempotent` to retry it",
+                    request.method
+                ),
+            )),
+            Ok(Some(ref retry)) => Ok(Some(retry)),
+            Ok(None) => Ok(self
+                .service_retry
+                .filter(|retry| idempotent || retry.non_idempotent)),
+            Err(ref err) => Err(err.clone()),
+        }
+    }
+
+    /// Returns the decoding of the body, the statements which take the metadata or parse
+    /// the `#[response(header = "...")]` values before the body is decoded,
+    /// and the adapter which returns them with the decoded body.
+    fn head(&self, decode: Decode) -> Result<(Decode, Option<TokenStream>, Option<TokenStream>)> {
+        let headers = match self.response {
+            Ok(ref response) => &response.headers[..],
+            Err(_) => &[],
+        };
+
+        if self.metadata {
+            if let Some(header) = headers.first() {
+                return Err(syn::Error::new(
+                    header.span(),
+                    "`Response<T>` already returns the headers of the response",
+                ));
+            }
+
+            return Ok((
+                decode,
+                Some(quote! { let head = retrofit::Response::head::<retrofit::Backend, _>(&res); }),
+                Some(quote! { .map(|body| head.map(|_| body)) }),
+            ));
+        }
+
+        if headers.is_empty() {
+            return Ok((decode, None, None));
+        }
+
+        let (body, types) = match self.output {
+            Some(syn::Type::Tuple(ref tuple)) if tuple.elems.len() == headers.len() + 1 => {
+                let body = match tuple.elems[0] {
+                    syn::Type::Tuple(ref unit) if unit.elems.is_empty() => Body::Unit,
+                    _ => Body::Decoded,
+                };
+
+                (body, tuple.elems.iter().skip(1).collect::<Vec<_>>())
+            }
+            Some(syn::Type::Tuple(ref tuple)) => {
+                return Err(syn::Error::new(
+                    tuple.span(),
+                    "expected a tuple of the body and the headers in order, \
+                     with `()` as the body to return the headers only",
+                ))
+            }
+            None => {
+                return Err(syn::Error::new(
+                    headers[0].span(),
+                    "expected a tuple of the body and the headers in order",
+                ))
+            }
+            Some(ref ty) if headers.len() == 1 => (Body::Unit, vec![ty]),
+            Some(_) => {
+                return Err(syn::Error::new(
+                    headers[1].span(),
+                    "expected a tuple of the headers in order",
+                ))
+            }
+        };
+        let names = (0..headers.len())
+            .map(|idx| format_ident!("header_{}", idx))
+            .collect::<Vec<_>>();
+        let values = headers
+            .iter()
+            .zip(types)
+            .map(|(name, ty)| match wrapped(ty, "Option") {
+                Some(ty) => {
+                    quote! { retrofit::header::parse_optional::<#ty>(res.headers(), #name) }
+                }
+                None => quote! { retrofit::header::parse::<#ty>(res.headers(), #name) },
+            });
+        let head = quote! {
+            #( let #names = #values; )*
+        };
+
+        Ok(match self.output {
+            Some(syn::Type::Tuple(_)) if body == Body::Decoded => (
+                decode,
+                Some(head),
+                Some(quote! { .and_then(|body| Ok((body, #( #names? ),*))) }),
+            ),
+            Some(syn::Type::Tuple(_)) => (
+                Decode::Backend(quote! { bytes() }),
+                Some(head),
+                Some(quote! { .and_then(|_| Ok(((), #( #names? ),*))) }),
+            ),
+            _ => (
+                Decode::Backend(quote! { bytes() }),
+                Some(head),
+                Some(quote! { .and_then(|_| Ok(#( #names? )*)) }),
+            ),
+        })
+    }
+
+    /// Returns the decoded body of `res`, with the `#[response(...)]` decoder
+    /// or the converter of the response `Content-Type`.
+    fn body(&self, decode: &Decode, mode: Mode, converters: TokenStream) -> TokenStream {
+        let await_token = mode.await_token();
+        let backend = match mode {
+            Mode::Blocking => quote! { retrofit::backend::blocking },
+            Mode::Async => quote! { retrofit::backend },
+        };
+
+        match decode {
+            Decode::Backend(decode) => quote! {
+                res.#decode #await_token .map_err(<retrofit::Backend as retrofit::backend::Backend>::error)
+            },
+            Decode::Xml(encoding) => quote! {
+                #backend::xml::<retrofit::Backend, _, _>(res, #converters, #encoding) #await_token
+            },
+            Decode::Format(media_type) => quote! {
+                #backend::decode_as::<retrofit::Backend, _, _>(res, #converters, #media_type) #await_token
+            },
+            Decode::Protobuf => quote! {
+                #backend::protobuf::<retrofit::Backend, _, _>(res) #await_token
+            },
+            Decode::Convert => quote! {
+                #backend::decode::<retrofit::Backend, _, _>(res, #converters) #await_token
+            },
+            Decode::Ndjson => {
+                let backend = mode.backend();
+
+                quote! { Ok(#backend::Ndjson::new(res)) }
+            }
+        }
+    }
+
+    /// Builds the multipart form from the `#[part]` arguments.
+    fn form(&self, params: &[Param], api_error: Option<&TokenStream>) -> Option<TokenStream> {
+        let parts = params
+            .iter()
+            .flat_map(|param| match param {
+                Param::Part { name, ident, kind } => {
+                    let fallible = if kind == "file" || kind == "json" {
+                        Some(quote! { #api_error ? })
+                    } else {
+                        None
+                    };
+
+                    Some(quote! {
+                        let form = <Self::Form as retrofit::backend::Form<retrofit::Backend>>::#kind(
+                            form, #name, &#ident,
+                        ) #fallible;
+                    })
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(quote! {
+                let form = <Self::Form as retrofit::backend::Form<retrofit::Backend>>::new();
+                #(#parts)*
+            })
+        }
+    }
+
+    /// Checks that the features of the formats used by `#[request(...)]` and `#[response(...)]` are enabled.
+    fn required_features(&self) -> Vec<TokenStream> {
+        let options = Args::extract("request", &self.attrs).unwrap_or_default();
+        let decode = match self.response {
+            Ok(response::Response {
+                decode: Some(syn::Expr::Call(ref call)),
+                ..
+            }) => match *call.func {
+                syn::Expr::Path(ref func) => func.path.get_ident(),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        options
+            .iter()
+            .map(|Arg { ident, .. }| ident)
+            .chain(decode)
+            .filter(|ident| {
+                ["xml", "msgpack", "cbor", "protobuf"]
+                    .iter()
+                    .any(|name| *ident == name)
+            })
+            .map(|ident| {
+                let require = format_ident!("__require_{}", ident, span = ident.span());
+
+                quote_spanned! { ident.span() => retrofit::#require!(); }
+            })
+            .collect()
+    }
+
+    /// Warns on the arguments which are used nowhere in the request.
+    fn unused_arguments(&self, request: &Request, params: &[Param]) -> Vec<TokenStream> {
+        self.sig
+            .inputs
+            .iter()
+            .flat_map(|input| match input {
+                syn::FnArg::Typed(arg) => match *arg.pat {
+                    syn::Pat::Ident(ref pat) => Some(&pat.ident),
+                    _ => None,
+                },
+                syn::FnArg::Receiver(_) => None,
+            })
+            .filter(|ident| {
+                !ident.to_string().starts_with('_')
+                    && !params.iter().any(|param| param.ident() == *ident)
+                    && !request.uses(ident)
+                    && !self
+                        .attrs
+                        .iter()
+                        .any(|attr| mentions(attr.tokens.clone(), ident))
+            })
+            .map(|ident| {
+                let name = format_ident!("unused_argument_{}", ident, span = ident.span());
+                let note = format!(
+                    "argument `{}` is not used by the request, \
+                     bind it with `#[path]`, `#[query]`, `#[header]` or `#[body]`",
+                    ident
+                );
+
+                quote_spanned! { ident.span() =>
+                    let _ = &#ident;
+                    {
+                        #[deprecated(note = #note)]
+                        #[allow(non_upper_case_globals)]
+                        const #name: () = ();
+                        #[allow(clippy::let_unit_value)]
+                        let _ = #name;
+                    }
+                }
+            })
+            .collect()
     }
 }
 
@@ -179,8 +977,48 @@ impl<'a> ToTokens for Method<'a> {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let sig = &self.sig;
 
+        let params = match self.params {
+            Ok(ref params) => params,
+            Err(ref err) => return self.error(err).to_tokens(tokens),
+        };
+
+        let decode = match self.response {
+            Ok(response::Response {
+                decode: Some(ref decode),
+                ..
+            }) => match Decode::new(decode) {
+                Ok(decode) => decode,
+                Err(err) => return self.error(&err).to_tokens(tokens),
+            },
+            Ok(_) => Decode::Convert,
+            Err(ref err) => return self.error(err).to_tokens(tokens),
+        };
+        let warnings;
+        let retry;
+        let encoded;
+        let url;
         let request = {
-            let request = Request::extract(self).expect("request");
+            let request = match Request::extract(self, params) {
+                Ok(request) => request,
+                Err(err) => return self.error(&err).to_tokens(tokens),
+            };
+
+            warnings = self
+                .unused_arguments(&request, params)
+                .into_iter()
+                .chain(self.required_features())
+                .collect::<Vec<_>>();
+            url = request.url();
+            retry = match self.retry_policy(&request) {
+                Ok(retry) => retry,
+                Err(err) => return self.error(&err).to_tokens(tokens),
+            };
+
+            let request = if self.call {
+                request.with_client(quote! { retrofit::Transport::client(self) })
+            } else {
+                request
+            };
             let headers = match Headers::extract("headers", &self.attrs) {
                 Ok(headers) => {
                     if headers.is_empty() {
@@ -191,35 +1029,354 @@ impl<'a> ToTokens for Method<'a> {
                 }
                 Err(err) => Some(err.to_compile_error()),
             };
-            let options = Args::extract("request", &self.attrs)
-                .expect("request")
+            let options = Args::extract("request", &self.attrs).expect("request");
+            let multipart = if params
+                .iter()
+                .any(|param| matches!(param, Param::Part { .. }))
+            {
+                let body = options.iter().find(|Arg { ident, .. }| {
+                    [
+                        "json",
+                        "xml",
+                        "msgpack",
+                        "cbor",
+                        "protobuf",
+                        "form",
+                        "body",
+                        "multipart",
+                    ]
+                    .iter()
+                    .any(|name| ident == name)
+                });
+
+                if let Some(Arg { ident, .. }) = body {
+                    let err = syn::Error::new(
+                        ident.span(),
+                        "the request body could not be combined with `#[part]` arguments",
+                    );
+
+                    return self.error(&err).to_tokens(tokens);
+                }
+
+                Some(quote! { .multipart(form) })
+            } else {
+                None
+            };
+            let (encoded_options, options) = options
                 .into_iter()
-                .map(|Arg { ident, expr, .. }| {
-                    if let Some(expr) = expr {
-                        quote! { .#ident(#expr) }
-                    } else {
-                        quote! { .#ident(#ident) }
+                .partition::<Vec<_>, _>(|Arg { ident, .. }| encode_format(ident).is_some());
+            let options = options.into_iter().map(|Arg { ident, expr, .. }| {
+                if ident == "protobuf" {
+                    let value = expr.map_or_else(|| quote! { #ident }, |expr| quote! { #expr });
+
+                    quote! {
+                        .header(retrofit::http::header::CONTENT_TYPE, retrofit::convert::APPLICATION_PROTOBUF)
+                        .body({
+                            use retrofit::convert::protobuf::Message as _;
+
+                            (#value).encode_to_vec()
+                        })
                     }
+                } else if let Some(expr) = expr {
+                    quote! { .#ident(#expr) }
+                } else {
+                    quote! { .#ident(#ident) }
+                }
+            });
+
+            encoded = encoded_options
+                .into_iter()
+                .next()
+                .map(|Arg { ident, expr, .. }| {
+                    let value = match expr {
+                        Some(expr) => quote! { #expr },
+                        None => quote! { #ident },
+                    };
+                    let media_type = encode_format(&ident).expect("format");
+
+                    (ident, value, media_type)
                 });
+            let encoded_body = encoded.as_ref().map(|(_, _, media_type)| {
+                quote! {
+                    .header(retrofit::http::header::CONTENT_TYPE, #media_type)
+                    .body(body)
+                }
+            });
+
+            let accept = if let Decode::Protobuf = decode {
+                Some(quote! {
+                    .header(retrofit::http::header::ACCEPT, retrofit::convert::APPLICATION_PROTOBUF)
+                })
+            } else {
+                None
+            };
 
             quote! {
                 #request
                     #headers
+                    #accept
                     #(#options)*
+                    #encoded_body
+                    #(#params)*
+                    #multipart
+            }
+        };
+
+        let (decode, head, with_head) = match self.head(decode) {
+            Ok(head) => head,
+            Err(err) => return self.error(&err).to_tokens(tokens),
+        };
+
+        let api_error = if self.api_error {
+            Some(quote! { .map_err(|err| err.api_error(&self.converters)) })
+        } else {
+            None
+        };
+        let form = self.form(params, api_error.as_ref());
+        let encode = params
+            .iter()
+            .find_map(|param| match param {
+                Param::Body {
+                    ident,
+                    media_type: Some(media_type),
+                    ..
+                } => Some((quote! { #media_type }, quote! { &#ident })),
+                Param::Body { kind, ident, .. } if kind == "json" => Some((
+                    quote! { retrofit::convert::APPLICATION_JSON },
+                    quote! { &#ident },
+                )),
+                _ => None,
+            })
+            .or_else(|| encoded.map(|(_, value, media_type)| (media_type, quote! { &(#value) })));
+
+        // The request of a `Call<T>` or `Paginate<T>` is built when the method is called,
+        // and a failed encoding is returned when it is sent.
+        let deferred = {
+            let build = quote! {{
+                let req = #request;
+                tracing::trace!(?req);
+                req.build().map_err(<retrofit::Backend as retrofit::backend::Backend>::error)
+            }};
+
+            let build = match encode {
+                Some((ref media_type, ref value)) => quote! {
+                    self.converters.encode(#media_type, #value).and_then(|body| #build)
+                },
+                None => build,
+            };
+
+            quote! { #url.and_then(|url| #build) }
+        };
+        let encode = encode.map(|(media_type, value)| {
+            quote! {
+                let body = self.converters.encode(#media_type, #value) #api_error ?;
+            }
+        });
+
+        if form.is_some() && (self.call || self.paginated.is_some()) {
+            let err = syn::Error::new(
+                self.sig.output.span(),
+                "`#[part]` arguments are only supported by a method returning the response",
+            );
+
+            return self.error(&err).to_tokens(tokens);
+        }
+
+        match self.paginate {
+            Ok(Some((ref paginate, _))) if self.paginated.is_some() => {
+                if let Ok(response::Response {
+                    decode: Some(ref decode),
+                    ..
+                }) = self.response
+                {
+                    let err = syn::Error::new(
+                        decode.span(),
+                        "the pages of a `#[paginate]` method are always decoded as JSON",
+                    );
+
+                    return self.error(&err).to_tokens(tokens);
+                }
+
+                let backend = self.mode.backend();
+                let retry = match retry {
+                    Some(retry) => quote! { Some(#retry) },
+                    None => quote! { None },
+                };
+                let error = if self.api_error {
+                    quote! { retrofit::Error::api_error }
+                } else {
+                    quote! { |err, _| err }
+                };
+                let expanded = quote! {
+                    #sig {
+                        #(#warnings)*
+                        #backend::Paginate::new(
+                            self.client().clone(),
+                            #deferred,
+                            self.interceptors.clone(),
+                            self.credentials.clone(),
+                            self.converters.clone(),
+                            #retry,
+                            #paginate,
+                            #error,
+                        )
+                    }
+                };
+
+                return expanded.to_tokens(tokens);
+            }
+            Ok(Some((_, span))) => {
+                let err =
+                    syn::Error::new(span, "a `#[paginate]` method should return `Paginate<T>`");
+
+                return self.error(&err).to_tokens(tokens);
+            }
+            Ok(None) => {
+                if let Some(span) = self.paginated {
+                    let err = syn::Error::new(
+                        span,
+                        "`Paginate<T>` should be returned by a `#[paginate(...)]` method",
+                    );
+
+                    return self.error(&err).to_tokens(tokens);
+                }
+            }
+            Err(ref err) => return self.error(err).to_tokens(tokens),
+        }
+
+        if self.download || self.output.as_ref().is_some_and(is_download) {
+            if let Ok(response::Response {
+                decode: Some(ref decode),
+                ..
+            }) = self.response
+            {
+                let err = syn::Error::new(decode.span(), "the body of a `Download` is streamed");
+
+                return self.error(&err).to_tokens(tokens);
+            }
+
+            if self.call {
+                let err = syn::Error::new(
+                    self.sig.output.span(),
+                    "`Download` should be returned without `Call<T>`",
+                );
+
+                return self.error(&err).to_tokens(tokens);
+            }
+        }
+
+        let ndjson_decode = matches!(decode, Decode::Ndjson);
+
+        if self.call
+            && self
+                .output
+                .as_ref()
+                .and_then(|ty| wrapped(ty, "Ndjson"))
+                .is_some()
+        {
+            let err = syn::Error::new(
+                self.sig.output.span(),
+                "`Ndjson<T>` should be returned without `Call<T>`",
+            );
+
+            return self.error(&err).to_tokens(tokens);
+        }
+
+        match self.ndjson {
+            Some(span) if !ndjson_decode => {
+                let err = syn::Error::new(
+                    span,
+                    "`Ndjson<T>` should be returned by a `#[response(ndjson())]` method",
+                );
+
+                return self.error(&err).to_tokens(tokens);
+            }
+            None if ndjson_decode => {
+                let err = syn::Error::new(
+                    self.sig.output.span(),
+                    "a `#[response(ndjson())]` method should return `Ndjson<T>`",
+                );
+
+                return self.error(&err).to_tokens(tokens);
             }
+            _ => {}
+        }
+
+        if self.call {
+            let converters = if let Decode::Backend(_) | Decode::Protobuf = decode {
+                quote! { _ }
+            } else {
+                quote! { converters }
+            };
+            let blocking_body = self.body(&decode, Mode::Blocking, quote! { converters });
+            let async_body = self.body(&decode, Mode::Async, quote! { &converters });
+            let with_retry = retry.map(|retry| quote! { .with_retry(#retry) });
+            let error = if self.api_error {
+                quote! { retrofit::Error::api_error }
+            } else {
+                quote! { |err, _| err }
+            };
+            let expanded = quote! {
+                #sig {
+                    #(#warnings)*
+                    retrofit::DeferredCall::new(
+                        self,
+                        #deferred,
+                        |res, #converters| {
+                            #head
+                            #blocking_body #with_head
+                        },
+                        |res, #converters| Box::pin(async move {
+                            #head
+                            #async_body #with_head
+                        }),
+                        #error,
+                    )
+                    #with_retry
+                }
+            };
+
+            return expanded.to_tokens(tokens);
+        }
+
+        let retry = match retry {
+            Some(retry) => quote! { Some(&#retry) },
+            None => quote! { None },
         };
+        let send = match self.mode {
+            Mode::Blocking => quote! {
+                retrofit::backend::blocking::execute::<retrofit::Backend, _>(
+                    self.client(), req.build(), &self.interceptors, self.credentials.as_deref(), #retry,
+                ) #api_error ?
+            },
+            Mode::Async => quote! {
+                retrofit::backend::execute::<retrofit::Backend, _>(
+                    self.client(), req.build(), &self.interceptors, self.credentials.as_deref(), #retry,
+                ).await #api_error ?
+            },
+        };
+        let response = if self.download {
+            let backend = self.mode.backend();
+
+            quote! { Ok(#backend::Download::new(res)) }
+        } else {
+            let body = self.body(&decode, self.mode, quote! { &self.converters });
 
-        let response = match response::extract(&self.attrs) {
-            Ok(Some(decode)) => quote! { res.#decode },
-            Ok(None) => quote! { res.json() },
-            Err(err) => err.to_compile_error(),
+            quote! {
+                #head
+                #body #with_head #api_error
+            }
         };
 
         let expanded = quote! {
             #sig {
+                #(#warnings)*
+                #form
+                #encode
+                let url = #url #api_error ?;
                 let req = #request;
                 tracing::trace!(?req);
-                let res = req.send()?;
+                let res = #send;
                 tracing::trace!(?res);
                 // tracing::trace!(text = %{
                 //     let mut buf: Vec<u8> = vec![];
@@ -0,0 +1,427 @@
+use std::error::Error as StdError;
+use std::fmt::Display;
+use std::path::Path;
+use std::time::Duration;
+
+use bytes::Bytes;
+use reqwest::Result;
+use serde::{de::DeserializeOwned, Serialize};
+
+use retrofit_core::{
+    backend::{self, BoxFuture},
+    Error,
+};
+
+use crate::{blocking, multipart, Body, Client, ClientBuilder, HeaderMap, Method, Request, Upload};
+
+/// The `reqwest` backend.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Reqwest;
+
+impl backend::Backend for Reqwest {
+    type Error = reqwest::Error;
+
+    fn error(err: reqwest::Error) -> Error {
+        if err.is_decode() {
+            Error::Deserialize(err.into())
+        } else if err.is_builder() {
+            let serialize = err.source().is_some_and(|source| {
+                source.is::<serde_json::Error>() || source.is::<serde_urlencoded::ser::Error>()
+            });
+
+            if serialize {
+                Error::Serialize(err.into())
+            } else {
+                Error::Request(err.into())
+            }
+        } else {
+            Error::Transport(err.into())
+        }
+    }
+
+    fn sleep(duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::delay_for(duration))
+    }
+}
+
+impl backend::ClientBuilder<Reqwest> for ClientBuilder {
+    type Client = Client;
+
+    fn user_agent(self, value: &'static str) -> Self {
+        ClientBuilder::user_agent(self, value)
+    }
+
+    fn default_headers(self, headers: HeaderMap) -> Self {
+        ClientBuilder::default_headers(self, headers)
+    }
+
+    fn build(self) -> Result<Client> {
+        ClientBuilder::build(self)
+    }
+}
+
+impl backend::Client<Reqwest> for Client {
+    type RequestBuilder = reqwest::RequestBuilder;
+
+    fn request(&self, method: Method, url: &str) -> Self::RequestBuilder {
+        Client::request(self, method, url)
+    }
+}
+
+impl backend::AsyncClient<Reqwest> for Client {
+    type Request = Request;
+    type Response = reqwest::Response;
+
+    fn execute(&self, req: Request) -> BoxFuture<'static, Result<reqwest::Response>> {
+        Box::pin(Client::execute(self, req))
+    }
+}
+
+impl backend::RequestBuilder<Reqwest> for reqwest::RequestBuilder {
+    type Body = Body;
+    type Form = multipart::Form;
+    type Request = Request;
+
+    fn build(self) -> Result<Request> {
+        reqwest::RequestBuilder::build(self)
+    }
+
+    fn try_clone(&self) -> Option<Self> {
+        reqwest::RequestBuilder::try_clone(self)
+    }
+
+    fn header(self, name: &str, value: &str) -> Self {
+        reqwest::RequestBuilder::header(self, name, value)
+    }
+
+    fn headers(self, headers: HeaderMap) -> Self {
+        reqwest::RequestBuilder::headers(self, headers)
+    }
+
+    fn query<T: Serialize + ?Sized>(self, query: &T) -> Self {
+        reqwest::RequestBuilder::query(self, query)
+    }
+
+    fn form<T: Serialize + ?Sized>(self, form: &T) -> Self {
+        reqwest::RequestBuilder::form(self, form)
+    }
+
+    fn json<T: Serialize + ?Sized>(self, json: &T) -> Self {
+        reqwest::RequestBuilder::json(self, json)
+    }
+
+    fn body<T: Into<Body>>(self, body: T) -> Self {
+        reqwest::RequestBuilder::body(self, body)
+    }
+
+    fn multipart(self, form: multipart::Form) -> Self {
+        reqwest::RequestBuilder::multipart(self, form)
+    }
+}
+
+impl backend::Form<Reqwest> for multipart::Form {
+    fn new() -> Self {
+        multipart::Form::new()
+    }
+
+    fn text<T: Display + ?Sized>(self, name: &str, value: &T) -> Self {
+        multipart::Form::text(self, name.to_owned(), value.to_string())
+    }
+
+    fn bytes<T: AsRef<[u8]> + ?Sized>(self, name: &str, value: &T) -> Self {
+        multipart::Form::part(
+            self,
+            name.to_owned(),
+            reqwest::multipart::Part::bytes(value.as_ref().to_vec()),
+        )
+    }
+
+    fn file<P: AsRef<Path> + ?Sized>(self, name: &str, path: &P) -> retrofit_core::Result<Self> {
+        let path = path.as_ref();
+        // The form is built synchronously, so only opening the file blocks, the body is streamed.
+        let file = std::fs::File::open(path).map_err(|err| Error::Request(err.into()))?;
+        let len = file
+            .metadata()
+            .map_err(|err| Error::Request(err.into()))?
+            .len();
+        let stream = tokio::io::reader_stream(tokio::fs::File::from_std(file));
+        let part = reqwest::multipart::Part::stream_with_length(Body::wrap_stream(stream), len);
+        let part = match path.file_name() {
+            Some(file_name) => part.file_name(file_name.to_string_lossy().into_owned()),
+            None => part,
+        };
+
+        Ok(multipart::Form::part(self, name.to_owned(), part))
+    }
+
+    fn json<T: Serialize + ?Sized>(self, name: &str, value: &T) -> retrofit_core::Result<Self> {
+        let json = serde_json::to_vec(value).map_err(|err| Error::Serialize(err.into()))?;
+        let part = reqwest::multipart::Part::bytes(json)
+            .mime_str("application/json")
+            .map_err(<Reqwest as backend::Backend>::error)?;
+
+        Ok(multipart::Form::part(self, name.to_owned(), part))
+    }
+}
+
+impl backend::Request<Reqwest> for Request {
+    fn method(&self) -> &Method {
+        Request::method(self)
+    }
+
+    fn method_mut(&mut self) -> &mut Method {
+        Request::method_mut(self)
+    }
+
+    fn url(&self) -> &str {
+        Request::url(self).as_str()
+    }
+
+    fn set_url(&mut self, url: &str) -> retrofit_core::Result<()> {
+        *Request::url_mut(self) = url.parse().map_err(|err| Error::Request(Box::new(err)))?;
+
+        Ok(())
+    }
+
+    fn headers_mut(&mut self) -> &mut HeaderMap {
+        Request::headers_mut(self)
+    }
+
+    fn try_clone(&self) -> Option<Self> {
+        Request::try_clone(self)
+    }
+}
+
+impl backend::AsyncRequestBuilder<Reqwest> for reqwest::RequestBuilder {
+    type Response = reqwest::Response;
+
+    fn upload(self, body: Upload) -> Self {
+        reqwest::RequestBuilder::body(self, Body::wrap_stream(body))
+    }
+
+    fn send(self) -> BoxFuture<'static, Result<Self::Response>> {
+        Box::pin(reqwest::RequestBuilder::send(self))
+    }
+}
+
+impl backend::Response<Reqwest> for reqwest::Response {
+    fn status(&self) -> http::StatusCode {
+        reqwest::Response::status(self)
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        reqwest::Response::headers(self)
+    }
+
+    fn headers_mut(&mut self) -> &mut HeaderMap {
+        reqwest::Response::headers_mut(self)
+    }
+
+    fn url(&self) -> &str {
+        reqwest::Response::url(self).as_str()
+    }
+}
+
+impl backend::AsyncResponse<Reqwest> for reqwest::Response {
+    fn json<T: DeserializeOwned + Send + 'static>(self) -> BoxFuture<'static, Result<T>> {
+        Box::pin(reqwest::Response::json(self))
+    }
+
+    fn text(self) -> BoxFuture<'static, Result<String>> {
+        Box::pin(reqwest::Response::text(self))
+    }
+
+    fn text_with_charset(self, default_encoding: &str) -> BoxFuture<'_, Result<String>> {
+        Box::pin(reqwest::Response::text_with_charset(self, default_encoding))
+    }
+
+    fn bytes(self) -> BoxFuture<'static, Result<Bytes>> {
+        Box::pin(reqwest::Response::bytes(self))
+    }
+
+    fn chunk(&mut self) -> BoxFuture<'_, Result<Option<Bytes>>> {
+        Box::pin(reqwest::Response::chunk(self))
+    }
+}
+
+impl backend::ClientBuilder<Reqwest> for blocking::ClientBuilder {
+    type Client = blocking::Client;
+
+    fn user_agent(self, value: &'static str) -> Self {
+        blocking::ClientBuilder::user_agent(self, value)
+    }
+
+    fn default_headers(self, headers: HeaderMap) -> Self {
+        blocking::ClientBuilder::default_headers(self, headers)
+    }
+
+    fn build(self) -> Result<blocking::Client> {
+        blocking::ClientBuilder::build(self)
+    }
+}
+
+impl backend::Client<Reqwest> for blocking::Client {
+    type RequestBuilder = reqwest::blocking::RequestBuilder;
+
+    fn request(&self, method: Method, url: &str) -> Self::RequestBuilder {
+        blocking::Client::request(self, method, url)
+    }
+}
+
+impl backend::blocking::Client<Reqwest> for blocking::Client {
+    type Request = blocking::Request;
+    type Response = blocking::Response;
+
+    fn execute(&self, req: blocking::Request) -> Result<blocking::Response> {
+        blocking::Client::execute(self, req)
+    }
+}
+
+impl backend::RequestBuilder<Reqwest> for reqwest::blocking::RequestBuilder {
+    type Body = blocking::Body;
+    type Form = blocking::multipart::Form;
+    type Request = blocking::Request;
+
+    fn build(self) -> Result<blocking::Request> {
+        reqwest::blocking::RequestBuilder::build(self)
+    }
+
+    fn try_clone(&self) -> Option<Self> {
+        reqwest::blocking::RequestBuilder::try_clone(self)
+    }
+
+    fn header(self, name: &str, value: &str) -> Self {
+        reqwest::blocking::RequestBuilder::header(self, name, value)
+    }
+
+    fn headers(self, headers: HeaderMap) -> Self {
+        reqwest::blocking::RequestBuilder::headers(self, headers)
+    }
+
+    fn query<T: Serialize + ?Sized>(self, query: &T) -> Self {
+        reqwest::blocking::RequestBuilder::query(self, query)
+    }
+
+    fn form<T: Serialize + ?Sized>(self, form: &T) -> Self {
+        reqwest::blocking::RequestBuilder::form(self, form)
+    }
+
+    fn json<T: Serialize + ?Sized>(self, json: &T) -> Self {
+        reqwest::blocking::RequestBuilder::json(self, json)
+    }
+
+    fn body<T: Into<blocking::Body>>(self, body: T) -> Self {
+        reqwest::blocking::RequestBuilder::body(self, body)
+    }
+
+    fn multipart(self, form: blocking::multipart::Form) -> Self {
+        reqwest::blocking::RequestBuilder::multipart(self, form)
+    }
+}
+
+impl backend::Form<Reqwest> for blocking::multipart::Form {
+    fn new() -> Self {
+        blocking::multipart::Form::new()
+    }
+
+    fn text<T: Display + ?Sized>(self, name: &str, value: &T) -> Self {
+        blocking::multipart::Form::text(self, name.to_owned(), value.to_string())
+    }
+
+    fn bytes<T: AsRef<[u8]> + ?Sized>(self, name: &str, value: &T) -> Self {
+        let part = reqwest::blocking::multipart::Part::bytes(value.as_ref().to_vec());
+
+        blocking::multipart::Form::part(self, name.to_owned(), part)
+    }
+
+    fn file<P: AsRef<Path> + ?Sized>(self, name: &str, path: &P) -> retrofit_core::Result<Self> {
+        blocking::multipart::Form::file(self, name.to_owned(), path.as_ref())
+            .map_err(|err| Error::Request(err.into()))
+    }
+
+    fn json<T: Serialize + ?Sized>(self, name: &str, value: &T) -> retrofit_core::Result<Self> {
+        let json = serde_json::to_vec(value).map_err(|err| Error::Serialize(err.into()))?;
+        let part = reqwest::blocking::multipart::Part::bytes(json)
+            .mime_str("application/json")
+            .map_err(<Reqwest as backend::Backend>::error)?;
+
+        Ok(blocking::multipart::Form::part(self, name.to_owned(), part))
+    }
+}
+
+impl backend::Request<Reqwest> for blocking::Request {
+    fn method(&self) -> &Method {
+        blocking::Request::method(self)
+    }
+
+    fn method_mut(&mut self) -> &mut Method {
+        blocking::Request::method_mut(self)
+    }
+
+    fn url(&self) -> &str {
+        blocking::Request::url(self).as_str()
+    }
+
+    fn set_url(&mut self, url: &str) -> retrofit_core::Result<()> {
+        *blocking::Request::url_mut(self) =
+            url.parse().map_err(|err| Error::Request(Box::new(err)))?;
+
+        Ok(())
+    }
+
+    fn headers_mut(&mut self) -> &mut HeaderMap {
+        blocking::Request::headers_mut(self)
+    }
+
+    fn try_clone(&self) -> Option<Self> {
+        blocking::Request::try_clone(self)
+    }
+}
+
+impl backend::blocking::RequestBuilder<Reqwest> for reqwest::blocking::RequestBuilder {
+    type Response = blocking::Response;
+
+    fn upload(self, body: blocking::Upload) -> Self {
+        reqwest::blocking::RequestBuilder::body(self, blocking::Body::new(body))
+    }
+
+    fn send(self) -> Result<blocking::Response> {
+        reqwest::blocking::RequestBuilder::send(self)
+    }
+}
+
+impl backend::Response<Reqwest> for blocking::Response {
+    fn status(&self) -> http::StatusCode {
+        blocking::Response::status(self)
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        blocking::Response::headers(self)
+    }
+
+    fn headers_mut(&mut self) -> &mut HeaderMap {
+        blocking::Response::headers_mut(self)
+    }
+
+    fn url(&self) -> &str {
+        blocking::Response::url(self).as_str()
+    }
+}
+
+impl backend::blocking::Response<Reqwest> for blocking::Response {
+    fn json<T: DeserializeOwned>(self) -> Result<T> {
+        blocking::Response::json(self)
+    }
+
+    fn text(self) -> Result<String> {
+        blocking::Response::text(self)
+    }
+
+    fn text_with_charset(self, default_encoding: &str) -> Result<String> {
+        blocking::Response::text_with_charset(self, default_encoding)
+    }
+
+    fn bytes(self) -> Result<Bytes> {
+        blocking::Response::bytes(self)
+    }
+}
@@ -0,0 +1,159 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::sync::Arc;
+
+use retrofit_core::{
+    backend::{self, BoxFuture},
+    AsyncCall, Call, Converters, CredentialProvider, Error, Interceptor, Retry,
+};
+
+use crate::{blocking, Client, Request, Reqwest, Response, Result};
+
+/// Provides the clients, interceptors, credentials and converters used to send a deferred call.
+pub trait Transport: Send + Sync {
+    /// Returns the async client.
+    fn client(&self) -> Client;
+
+    /// Returns the blocking client.
+    fn blocking_client(&self) -> blocking::Client;
+
+    /// Returns the interceptors of the service.
+    fn interceptors(&self) -> &[Arc<dyn Interceptor>];
+
+    /// Returns the credential provider of the service.
+    fn credentials(&self) -> Option<&dyn CredentialProvider>;
+
+    /// Returns the converters of the service.
+    fn converters(&self) -> &Converters;
+}
+
+/// A deferred request which could be sent with the blocking or async client.
+///
+/// **Note**: Only buffered bodies could be sent with the blocking client,
+/// a streaming body or a multipart form must be sent with `async_send`,
+/// or `send` returns `Error::Request`.
+pub struct DeferredCall<'a, T, E = Infallible> {
+    transport: &'a dyn Transport,
+    request: Result<Request>,
+    decode: fn(blocking::Response, &Converters) -> Result<T>,
+    async_decode: fn(Response, Converters) -> BoxFuture<'static, Result<T>>,
+    error: fn(Error, &Converters) -> Error<E>,
+    retry: Option<Retry>,
+}
+
+impl<'a, T, E> DeferredCall<'a, T, E> {
+    pub fn new(
+        transport: &'a dyn Transport,
+        request: Result<Request>,
+        decode: fn(blocking::Response, &Converters) -> Result<T>,
+        async_decode: fn(Response, Converters) -> BoxFuture<'static, Result<T>>,
+        error: fn(Error, &Converters) -> Error<E>,
+    ) -> Self {
+        DeferredCall {
+            transport,
+            request,
+            decode,
+            async_decode,
+            error,
+            retry: None,
+        }
+    }
+
+    /// Sends the request with the retry policy.
+    pub fn with_retry(self, retry: Retry) -> Self {
+        DeferredCall {
+            retry: Some(retry),
+            ..self
+        }
+    }
+}
+
+impl<'a, T, E> AsyncCall<T> for DeferredCall<'a, T, E>
+where
+    T: Send + 'a,
+    E: fmt::Debug + Send + Sync + 'a,
+{
+    type Error = Error<E>;
+    type Future = BoxFuture<'a, Result<T, Error<E>>>;
+
+    fn async_send(self) -> Self::Future {
+        let DeferredCall {
+            transport,
+            request,
+            async_decode,
+            error,
+            retry,
+            ..
+        } = self;
+
+        Box::pin(async move {
+            async move {
+                let res = backend::execute::<Reqwest, _>(
+                    &transport.client(),
+                    Ok(request?),
+                    transport.interceptors(),
+                    transport.credentials(),
+                    retry.as_ref(),
+                )
+                .await?;
+                tracing::trace!(?res);
+                async_decode(res, transport.converters().clone()).await
+            }
+            .await
+            .map_err(|err| error(err, transport.converters()))
+        })
+    }
+}
+
+impl<'a, T, E> Call<T> for DeferredCall<'a, T, E>
+where
+    T: Send + 'a,
+    E: fmt::Debug + Send + Sync + 'a,
+{
+    fn send(self) -> Result<T, Error<E>> {
+        let DeferredCall {
+            transport,
+            request,
+            decode,
+            error,
+            retry,
+            ..
+        } = self;
+
+        let res = || {
+            let request = into_blocking(request?)?;
+            let res = backend::blocking::execute::<Reqwest, _>(
+                &transport.blocking_client(),
+                Ok(request),
+                transport.interceptors(),
+                transport.credentials(),
+                retry.as_ref(),
+            )?;
+            tracing::trace!(?res);
+            decode(res, transport.converters())
+        };
+
+        res().map_err(|err| error(err, transport.converters()))
+    }
+}
+
+/// Converts the async request for the blocking client, a streaming body is returned as `Error::Request`.
+fn into_blocking(req: Request) -> Result<blocking::Request> {
+    let mut blocking = blocking::Request::new(req.method().clone(), req.url().clone());
+
+    *blocking.headers_mut() = req.headers().clone();
+    *blocking.timeout_mut() = req.timeout().cloned();
+    *blocking.body_mut() = match req.body() {
+        Some(body) => match body.as_bytes() {
+            Some(bytes) => Some(bytes.to_vec().into()),
+            None => {
+                return Err(Error::Request(
+                    "only buffered body could be sent with the blocking client".into(),
+                ))
+            }
+        },
+        None => None,
+    };
+
+    Ok(blocking)
+}
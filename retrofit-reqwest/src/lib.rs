@@ -1,16 +1,57 @@
 pub extern crate reqwest;
 
-pub type Error = reqwest::Error;
-pub type Result<T> = reqwest::Result<T>;
+use std::convert::Infallible;
+
+mod backend;
+mod call;
+
+pub use self::backend::Reqwest;
+pub use self::call::{DeferredCall, Transport};
+pub use retrofit_core::{Error, Result};
+
+/// The marker type of the `reqwest` backend.
+pub type Backend = Reqwest;
 pub type Method = reqwest::Method;
 pub type HeaderMap = reqwest::header::HeaderMap;
 pub type HeaderValue = reqwest::header::HeaderValue;
 
+pub type Client = reqwest::Client;
+pub type ClientBuilder = reqwest::ClientBuilder;
+pub type Request = reqwest::Request;
+pub type Response = reqwest::Response;
+pub type Body = reqwest::Body;
+pub type Upload = retrofit_core::upload::Upload;
+pub mod multipart {
+    pub type Form = reqwest::multipart::Form;
+}
+
+/// A `Stream` of the items returned by a paginated method.
+pub type Paginate<T, E = Infallible> = retrofit_core::paginate::Pages<Reqwest, Client, T, E>;
+
+/// A `Stream` of the chunks of a response body.
+pub type Download = retrofit_core::download::Download<Reqwest, Response>;
+
+/// A `Stream` of the lines of a newline-delimited JSON response body.
+pub type Ndjson<T> = retrofit_core::ndjson::Ndjson<Reqwest, Response, T>;
+
 pub mod blocking {
     pub type Client = reqwest::blocking::Client;
     pub type ClientBuilder = reqwest::blocking::ClientBuilder;
+    pub type Request = reqwest::blocking::Request;
+    pub type Response = reqwest::blocking::Response;
     pub type Body = reqwest::blocking::Body;
+    pub type Upload = retrofit_core::upload::blocking::Upload;
     pub mod multipart {
         pub type Form = reqwest::blocking::multipart::Form;
     }
+
+    /// An `Iterator` of the items returned by a paginated method.
+    pub type Paginate<T, E = super::Infallible> =
+        retrofit_core::paginate::blocking::Pages<super::Reqwest, Client, T, E>;
+
+    /// A `Read` of a response body.
+    pub type Download = retrofit_core::download::blocking::Download<super::Reqwest, Response>;
+
+    /// An `Iterator` of the lines of a newline-delimited JSON response body.
+    pub type Ndjson<T> = retrofit_core::ndjson::blocking::Ndjson<super::Reqwest, Response, T>;
 }
@@ -20,6 +20,7 @@ const GITHUB_JSON_PREVIEW: &str = "application/vnd.github.mercy-preview+json";
     user_agent = "gh/1.0",
 )]
 #[default_headers(accept = GITHUB_JSON_V3)]
+#[auth(bearer)]
 pub trait GithubService {
     /// Get a repository
     #[get("/repos/{owner}/{repo}")]
@@ -37,7 +38,8 @@ pub trait GithubService {
     /// List repositories for a user
     #[get("/users/{username}/repos")]
     #[request(query)]
-    fn list_repo(&self, username: &str, query: &ListRepo) -> Vec<Repo>;
+    #[paginate(link_header)]
+    fn list_repo(&self, username: &str, query: &ListRepo) -> Paginate<Repo>;
 
     /// List repository languages
     #[get("/repos/{owner}/{repo}/languages")]
@@ -46,7 +48,12 @@ pub trait GithubService {
     /// List repository tags
     #[get("/repos/{owner}/{repo}/tags")]
     #[request(query = pagination)]
-    fn list_repo_tags(&self, owner: &str, repo: &str, pagination: &Pagination) -> Vec<Tag>;
+    #[paginate(link_header)]
+    fn list_repo_tags(&self, owner: &str, repo: &str, pagination: &Pagination) -> Paginate<Tag>;
+
+    /// Download a tarball archive of the repository
+    #[get("/repos/{owner}/{repo}/tarball/{reference}")]
+    fn download_tarball(&self, owner: &str, repo: &str, reference: &str) -> Download;
 
     /// List repository teams
     #[get("/repos/{owner}/{repo}/teams")]
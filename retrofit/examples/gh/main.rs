@@ -1,6 +1,11 @@
+use std::fs::File;
+use std::io;
+use std::path::PathBuf;
+
 use anyhow::Result;
 use structopt::StructOpt;
 
+#[allow(dead_code, unused_imports)]
 mod github;
 
 use self::github::service::*;
@@ -8,6 +13,14 @@ use self::github::service::*;
 #[derive(Debug, StructOpt)]
 #[structopt(about = "Work seamlessly with GitHub from the command line.")]
 pub struct Opt {
+    /// The base URL of the GitHub API
+    #[structopt(long)]
+    pub base_url: Option<String>,
+
+    /// The personal access token to authenticate with
+    #[structopt(long, env = "GITHUB_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+
     #[structopt(flatten)]
     pub pagination: Pagination,
 
@@ -93,6 +106,17 @@ pub enum Repo {
     Languages { owner: String, repo: String },
     /// List repository tags
     Tags { owner: String, repo: String },
+    /// Download a tarball archive of the repository
+    Tarball {
+        owner: String,
+        repo: String,
+        /// The branch, tag or commit to archive.
+        reference: String,
+
+        /// Write the archive to the file instead of stdout.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+    },
     /// List repository teams
     Teams { owner: String, repo: String },
     /// Get all repository topics
@@ -104,7 +128,14 @@ fn main() -> Result<()> {
 
     let opt = Opt::from_args();
 
-    let github = github_service();
+    let mut builder = GithubServiceClient::builder();
+    if let Some(base_url) = opt.base_url {
+        builder = builder.base_url(base_url);
+    }
+    if let Some(token) = opt.token {
+        builder = builder.bearer_auth(token);
+    }
+    let github = builder.build();
 
     match opt.cmd {
         Cmd::Repo { repo } => match repo {
@@ -122,7 +153,9 @@ fn main() -> Result<()> {
                         direction,
                         pagination: opt.pagination,
                     },
-                )? {
+                ) {
+                    let repo = repo?;
+
                     println!(
                         "{:40} watch: {:>4}, star: {:>4}, fork: {:>4}",
                         repo.name, repo.watchers_count, repo.stargazers_count, repo.forks_count
@@ -135,10 +168,25 @@ fn main() -> Result<()> {
                 }
             }
             Repo::Tags { owner, repo } => {
-                for tag in github.list_repo_tags(&owner, &repo, &opt.pagination)? {
+                for tag in github.list_repo_tags(&owner, &repo, &opt.pagination) {
+                    let tag = tag?;
+
                     println!("{}\t#{}", tag.name, &tag.commit.sha[..8]);
                 }
             }
+            Repo::Tarball {
+                owner,
+                repo,
+                reference,
+                output,
+            } => {
+                let mut tarball = github.download_tarball(&owner, &repo, &reference)?;
+
+                match output {
+                    Some(path) => tarball.copy_to(&mut File::create(path)?)?,
+                    None => tarball.copy_to(&mut io::stdout())?,
+                };
+            }
             Repo::Teams { owner, repo } => {
                 for team in github.list_repo_teams(&owner, &repo, &opt.pagination)? {
                     println!("{}\t{}", team.name, team.description.unwrap_or_default());
@@ -1,9 +1,530 @@
-pub use retrofit_core::{Call, Service};
-pub use retrofit_macros::{args, client, delete, options, patch, post, put, service, trace};
+pub use retrofit_core::{
+    backend, convert, header, http, path, query, upload, AsyncCall, Backoff, Converter, Converters,
+    CredentialProvider, Credentials, Interceptor, Pagination, Response, Retry, Service,
+};
+pub use retrofit_macros::{args, client, delete, options, patch, post, put, trace};
 
-#[cfg(feature = "reqwest-client")]
 #[doc(hidden)]
-pub use retrofit_reqwest::*;
+pub use async_trait::async_trait;
+#[doc(hidden)]
+pub use once_cell::sync::OnceCell;
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "reqwest-client")] {
+        #[doc(hidden)]
+        pub use retrofit_reqwest::*;
+    } else {
+        compile_error!("no HTTP backend selected, please enable a backend feature, e.g. `reqwest-client`");
+    }
+}
+
+/// Defines the macros invoked by a method using a format, which fail to compile without its feature.
+macro_rules! require_features {
+    ($($feature:literal => $name:ident,)*) => {$(
+        #[cfg(feature = $feature)]
+        #[doc(hidden)]
+        #[macro_export]
+        macro_rules! $name {
+            () => {};
+        }
+
+        #[cfg(not(feature = $feature))]
+        #[doc(hidden)]
+        #[macro_export]
+        macro_rules! $name {
+            () => {
+                compile_error!(concat!("the `", $feature, "` feature of `retrofit` should be enabled"));
+            };
+        }
+    )*};
+}
+
+require_features! {
+    "xml" => __require_xml,
+    "msgpack" => __require_msgpack,
+    "cbor" => __require_cbor,
+    "protobuf" => __require_protobuf,
+}
+
+/// Generate a client for the HTTP API described by a trait.
+///
+/// The generated client `HttpBinClient` for `trait HttpBin` is returned by a function named after the trait
+/// in snake case, e.g. `http_bin()`, or constructed with a builder.
+///
+/// # Blocking
+///
+/// By default, every method sends the request with the blocking client and waits for the response.
+///
+/// ## Example
+///
+/// ```
+/// # use retrofit::{service, get, response};
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[get("/base64/{value}")]
+///     #[response(text())]
+///     fn base64(&self, value: &str) -> String;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let res = http_bin().base64("SFRUUEJJTiBpcyBhd2Vzb21l")?;
+/// assert_eq!(res, "HTTPBIN is awesome");
+/// # Ok(()) }
+/// ```
+///
+/// # Async
+///
+/// Use `async` to send the request with the async client, every method becomes an `async fn`
+/// which returns a `Send` future, the request attributes work unchanged.
+///
+/// ## Example
+///
+/// ```
+/// # use retrofit::{service, get, response};
+/// #[service(base_url = "http://httpbin.org", async)]
+/// pub trait HttpBin {
+///     #[get("/base64/{value}")]
+///     #[response(text())]
+///     async fn base64(&self, value: &str) -> String;
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> retrofit::Result<()> {
+/// let res = http_bin().base64("SFRUUEJJTiBpcyBhd2Vzb21l").await?;
+/// assert_eq!(res, "HTTPBIN is awesome");
+/// # Ok(()) }
+/// ```
+///
+/// # Builder
+///
+/// Use `HttpBinClient::builder()` to override the `base_url` at runtime,
+/// e.g. point the same trait at a staging server or a local mock server,
+/// or to use a preconfigured client or client builder.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use retrofit::{service, get, response, blocking::Client};
+/// #[service(base_url = "https://httpbin.example.com")]
+/// pub trait HttpBin {
+///     #[get("/base64/{value}")]
+///     #[response(text())]
+///     fn base64(&self, value: &str) -> String;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let httpbin = HttpBinClient::builder()
+///     .base_url("http://httpbin.org")
+///     .client_builder(Client::builder().timeout(Duration::from_secs(5)))
+///     .build();
+///
+/// let res = httpbin.base64("SFRUUEJJTiBpcyBhd2Vzb21l")?;
+/// assert_eq!(res, "HTTPBIN is awesome");
+/// # Ok(()) }
+/// ```
+///
+/// # Interceptors
+///
+/// An [`Interceptor`] registered with `HttpBinClient::builder().interceptor(...)`
+/// could inspect or mutate the requests and responses of every method, e.g. logging, auth or metrics.
+/// A closure takes the outgoing request only.
+///
+/// ## Example
+///
+/// ```
+/// # use retrofit::{service, get, http::HeaderValue};
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[get("/headers")]
+///     fn headers(&self) -> serde_json::Value;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let httpbin = HttpBinClient::builder()
+///     .interceptor(|req: &mut retrofit::http::Request<()>| {
+///         req.headers_mut()
+///             .insert("x-request-id", HeaderValue::from_static("42"));
+///
+///         Ok(())
+///     })
+///     .build();
+///
+/// let res = httpbin.headers()?;
+/// assert_eq!(res["headers"]["X-Request-Id"], "42");
+/// # Ok(()) }
+/// ```
+///
+/// # Converters
+///
+/// A [`Converter`] registered with `HttpBinClient::builder().converter(...)` encodes the request bodies
+/// and decodes the response bodies of its media type, so that a crate could add a format.
+///
+/// The argument of `#[body("media/type")]` is encoded with the converter of the media type,
+/// and sent with the `Content-Type` header, as the JSON bodies of `#[body]` and `#[request(json = ..)]`
+/// are encoded with the converter of `application/json`. The body of a `Call<T>` or `Paginate<T>`
+/// is encoded when the method is called, and a failed encoding is returned when it is sent.
+/// The response body is decoded with the converter
+/// of its `Content-Type`, or as JSON, unless the method declares a `#[response(...)]` decoder.
+///
+/// ## Example
+///
+/// ```
+/// # use serde::Serialize;
+/// # use retrofit::{service, post, convert::{erased_serde, Json}, Converter};
+/// /// Sends and receives JSON as `text/plain`.
+/// struct PlainJson;
+///
+/// impl Converter for PlainJson {
+///     fn media_type(&self) -> &str {
+///         "text/plain"
+///     }
+///
+///     fn encode(&self, value: &dyn erased_serde::Serialize) -> retrofit::Result<Vec<u8>> {
+///         Json.encode(value)
+///     }
+///
+///     fn decode(
+///         &self,
+///         body: &[u8],
+///         visit: &mut dyn FnMut(&mut dyn erased_serde::Deserializer<'_>) -> erased_serde::Result<()>,
+///     ) -> retrofit::Result<()> {
+///         Json.decode(body, visit)
+///     }
+/// }
+///
+/// #[derive(Serialize)]
+/// pub struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[post("/anything")]
+///     fn anything(&self, #[body("text/plain")] point: &Point) -> serde_json::Value;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let httpbin = HttpBinClient::builder().converter(PlainJson).build();
+///
+/// let res = httpbin.anything(&Point { x: 1, y: 2 })?;
+/// assert_eq!(res["headers"]["Content-Type"], "text/plain");
+/// assert_eq!(res["data"], r#"{"x":1,"y":2}"#);
+/// # Ok(()) }
+/// ```
+///
+/// # Parameters
+///
+/// The role of an argument could be declared with a parameter attribute,
+/// otherwise it is only used by the `{name}` placeholder in the path with the same name.
+///
+/// - `#[path]` or `#[path("name")]` replaces the `{name}` placeholder in the path.
+/// - `#[query]` or `#[query("name")]` appends a `name=value` pair to the query string.
+/// - `#[header]` or `#[header("name")]` adds a header, the name defaults to the argument with `-` for `_`.
+/// - `#[body]` sends the argument as JSON, `#[body(form)]` as a form and `#[body(raw)]` as is,
+///   `#[body(upload)]` streams a `Self::Upload`, see [`request`](attr.request.html#streaming-body),
+///   `#[body("media/type")]` encodes it with the converter of the media type, see [converters](#converters).
+/// - `#[part]` or `#[part("name")]` adds a part to the `multipart/form-data` body,
+///   see [`request`](attr.request.html#multipart-form).
+///
+/// ## Example
+///
+/// ```
+/// # use serde::Serialize;
+/// # use retrofit::{service, get, post};
+/// #[derive(Serialize)]
+/// pub struct Login {
+///     user: String,
+/// }
+///
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[get("/anything/{id}")]
+///     fn search(
+///         &self,
+///         #[path("id")] repo: &str,
+///         #[query("q")] term: &str,
+///         #[header("X-Request-Id")] request_id: &str,
+///     ) -> serde_json::Value;
+///
+///     #[post("/anything")]
+///     fn login(&self, #[body] login: &Login) -> serde_json::Value;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let res = http_bin().search("retrofit", "rust", "42")?;
+/// assert_eq!(res["url"], "http://httpbin.org/anything/retrofit?q=rust");
+/// assert_eq!(res["headers"]["X-Request-Id"], "42");
+///
+/// let res = http_bin().login(&Login { user: "flier".into() })?;
+/// assert_eq!(res["json"]["user"], "flier");
+/// # Ok(()) }
+/// ```
+///
+/// ## Validation
+///
+/// Every `{name}` placeholder in the path must be bound to an argument of the method,
+/// otherwise it fails to compile. An argument used nowhere in the request is warned.
+///
+/// The error points at the placeholder with a nightly compiler,
+/// or at the whole path on stable, which can't point inside a string literal.
+///
+/// ```compile_fail
+/// # use retrofit::{service, get};
+/// #[service(base_url = "https://api.github.com")]
+/// pub trait Github {
+///     #[get("/repos/{owner}/{repo}")]
+///     fn repo(&self, owner: &str) -> serde_json::Value;
+/// }
+/// ```
+///
+/// ## Query parameters
+///
+/// A `None` query parameter is omitted, a `Vec<T>` or slice is expanded as repeated keys,
+/// or joined with comma by `#[query("name", style = "csv")]`.
+///
+/// ```
+/// # use retrofit::{service, get};
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[get("/anything")]
+///     fn search(
+///         &self,
+///         #[query] page: Option<u32>,
+///         #[query("id")] ids: &[u32],
+///         #[query("tag", style = "csv")] tags: Vec<&str>,
+///     ) -> serde_json::Value;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let res = http_bin().search(None, &[1, 2], vec!["a", "b"])?;
+/// assert_eq!(res["args"]["id"], serde_json::json!(["1", "2"]));
+/// assert_eq!(res["args"]["tag"], "a,b");
+/// assert!(res["args"].get("page").is_none());
+/// # Ok(()) }
+/// ```
+///
+/// # Response metadata
+///
+/// A method declared to return `Response<T>` keeps the status, headers and final URL
+/// of the response beside the decoded body, e.g. the `ETag` or rate limit headers.
+///
+/// ## Example
+///
+/// ```
+/// # use retrofit::{service, get};
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[get("/response-headers")]
+///     fn headers(&self, #[query("ETag")] etag: &str) -> Response<serde_json::Value>;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let res = http_bin().headers("abc")?;
+/// assert_eq!(res.status(), 200);
+/// assert_eq!(res.headers()["etag"], "abc");
+/// assert_eq!(res.url(), "http://httpbin.org/response-headers?ETag=abc");
+/// assert_eq!(res.body()["ETag"], "abc");
+/// # Ok(()) }
+/// ```
+///
+/// # Downloads
+///
+/// A method declared to return `Download` streams the response body on demand,
+/// without holding the full body in memory, e.g. a tarball archive.
+///
+/// The blocking `Download` is a `std::io::Read` of the body, the async `Download`
+/// is a `Stream` of the `Bytes` chunks. Both may `copy_to` a `std::io::Write`.
+///
+/// ## Example
+///
+/// ```
+/// # use std::io::Read;
+/// # use retrofit::{service, get};
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[get("/bytes/{len}")]
+///     fn download(&self, len: usize) -> Download;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let bin = http_bin();
+/// let mut body = vec![];
+/// bin.download(1024)?.read_to_end(&mut body).unwrap();
+/// assert_eq!(body.len(), 1024);
+///
+/// let mut file = vec![];
+/// assert_eq!(bin.download(2048)?.copy_to(&mut file)?, 2048);
+/// # Ok(()) }
+/// ```
+///
+/// # Errors
+///
+/// Every method returns a `retrofit::Error`, which distinguishes building the request,
+/// serializing the request, transport, HTTP status and deserializing the response errors.
+///
+/// A non-success response is not decoded, but returned as `Error::Status`
+/// with the status code, headers and raw body preserved.
+///
+/// ## Example
+///
+/// ```
+/// # use retrofit::{service, get, Error};
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[get("/status/{code}")]
+///     fn status(&self, code: u16) -> serde_json::Value;
+/// }
+///
+/// match http_bin().status(404) {
+///     Err(Error::Status(err)) => {
+///         assert_eq!(err.status, 404);
+///         println!("{}", String::from_utf8_lossy(&err.body));
+///     }
+///     res => panic!("unexpected response: {:?}", res),
+/// }
+/// ```
+///
+/// ## Typed error body
+///
+/// Declare the error body of the API with `#[service(error = ApiError)]` for every method,
+/// or `#[response(error = ApiError)]` for a single method, the body of a non-success response
+/// is decoded with the converter of its `Content-Type`, or as JSON, and returned as `Error::Api`
+/// with the status code and headers of the response.
+///
+/// The `Error::Status` is returned as is if the body could not be decoded.
+///
+/// ```no_run
+/// # use serde::Deserialize;
+/// # use retrofit::{service, default_headers, get, Error};
+/// #[derive(Debug, Deserialize)]
+/// pub struct ApiError {
+///     message: String,
+///     documentation_url: String,
+/// }
+///
+/// #[service(base_url = "https://api.github.com", error = ApiError)]
+/// #[default_headers(accept = "application/vnd.github.v3+json")]
+/// pub trait Github {
+///     #[get("/repos/{owner}/{repo}")]
+///     fn repo(&self, owner: &str, repo: &str) -> serde_json::Value;
+/// }
+///
+/// match github().repo("flier", "not-found") {
+///     Err(Error::Api(err)) => {
+///         assert_eq!(err.status, 404);
+///         assert_eq!(err.error.message, "Not Found");
+///     }
+///     res => panic!("unexpected response: {:?}", res),
+/// }
+/// ```
+///
+/// # Thread safety
+///
+/// The generated client is `Send + Sync + Clone`, the clones share the same underlying HTTP client,
+/// so one instance could be shared application-wide.
+///
+/// ## Example
+///
+/// ```
+/// # use std::thread;
+/// # use retrofit::{service, get, response};
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[get("/base64/{value}")]
+///     #[response(text())]
+///     fn base64(&self, value: &str) -> String;
+/// }
+///
+/// let httpbin = http_bin();
+/// let handles = (0..2)
+///     .map(|_| {
+///         let httpbin = httpbin.clone();
+///
+///         thread::spawn(move || httpbin.base64("SFRUUEJJTiBpcyBhd2Vzb21l"))
+///     })
+///     .collect::<Vec<_>>();
+///
+/// for handle in handles {
+///     assert_eq!(handle.join().unwrap().unwrap(), "HTTPBIN is awesome");
+/// }
+/// ```
+pub use retrofit_macros::service;
+
+/// A deferred request which could be sent blocking or async.
+///
+/// Declare a method returning `Call<T>` to get the request without sending it,
+/// then use `send` to send it with the blocking client, or `async_send` with the async client.
+///
+/// **Note**: Only buffered bodies could be sent with the blocking client,
+/// the body and form arguments use the async `retrofit::Body` and `retrofit::multipart::Form`.
+///
+/// # Example
+///
+/// Sending the request with the blocking client:
+/// ```
+/// # use retrofit::{service, get, response, Call};
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[get("/base64/{value}")]
+///     #[response(text())]
+///     fn base64(&self, value: &str) -> Call<String>;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let httpbin = http_bin();
+/// let res = httpbin.base64("SFRUUEJJTiBpcyBhd2Vzb21l").send()?;
+/// assert_eq!(res, "HTTPBIN is awesome");
+/// # Ok(()) }
+/// ```
+///
+/// Sending the same request with the async client:
+/// ```
+/// # use retrofit::{service, get, response, AsyncCall, Call};
+/// # #[service(base_url = "http://httpbin.org")]
+/// # pub trait HttpBin {
+/// #     #[get("/base64/{value}")]
+/// #     #[response(text())]
+/// #     fn base64(&self, value: &str) -> Call<String>;
+/// # }
+/// # #[tokio::main]
+/// # async fn main() -> retrofit::Result<()> {
+/// let httpbin = http_bin();
+/// let res = httpbin.base64("SFRUUEJJTiBpcyBhd2Vzb21l").async_send().await?;
+/// assert_eq!(res, "HTTPBIN is awesome");
+/// # Ok(()) }
+/// ```
+///
+/// # Clients
+///
+/// A call is sent with the client of the service, or a client of the other flavor,
+/// which doesn't share the `client_builder` or `client` passed to the builder.
+/// Configure it with `async_client_builder` or `async_client` for a blocking service,
+/// and `blocking_client_builder` or `blocking_client` for an async service.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use retrofit::{service, get, response, AsyncCall, Call, Client};
+/// # #[service(base_url = "http://httpbin.org")]
+/// # pub trait HttpBin {
+/// #     #[get("/base64/{value}")]
+/// #     #[response(text())]
+/// #     fn base64(&self, value: &str) -> Call<String>;
+/// # }
+/// # #[tokio::main]
+/// # async fn main() -> retrofit::Result<()> {
+/// let httpbin = HttpBinClient::builder()
+///     .async_client_builder(Client::builder().timeout(Duration::from_secs(5)))
+///     .build();
+///
+/// let res = httpbin.base64("SFRUUEJJTiBpcyBhd2Vzb21l").async_send().await?;
+/// assert_eq!(res, "HTTPBIN is awesome");
+/// # Ok(()) }
+/// ```
+pub use retrofit_core::Call;
 
 /// Make a GET request.
 ///
@@ -19,38 +540,177 @@ pub use retrofit_reqwest::*;
 /// }
 ///
 /// # fn main() -> retrofit::Result<()> {
-/// let res = http_bin().base64("SFRUUEJJTiBpcyBhd2Vzb21l")?;
-/// assert_eq!(res, "HTTPBIN is awesome");
+/// let res = http_bin().base64("SFRUUEJJTiBpcyBhd2Vzb21l")?;
+/// assert_eq!(res, "HTTPBIN is awesome");
+/// # Ok(()) }
+/// ```
+///
+/// # Path parameters
+///
+/// The `{name}` placeholders in the path are percent-encoded as a single path segment,
+/// use `{name:raw}` for the parameters that intentionally contain slashes.
+/// A `.` or `..` value is returned as `Error::Request`, since it would be removed from the URL.
+///
+/// ## Example
+///
+/// ```
+/// # use retrofit::{service, get};
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[get("/anything/{branch}")]
+///     fn branch(&self, branch: &str) -> serde_json::Value;
+///
+///     #[get("/anything/{path:raw}")]
+///     fn path(&self, path: &str) -> serde_json::Value;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let res = http_bin().branch("feature/a b")?;
+/// assert_eq!(res["url"], "http://httpbin.org/anything/feature%2Fa%20b");
+///
+/// let res = http_bin().path("docs/index.html")?;
+/// assert_eq!(res["url"], "http://httpbin.org/anything/docs/index.html");
+/// # Ok(()) }
+/// ```
+pub use retrofit_macros::get;
+
+/// Use a custom HTTP verb for a request.
+///
+/// # Example
+///
+/// ```
+/// # use retrofit::{service, http};
+/// # #[service(base_url = "http://httpbin.org")]
+/// # pub trait HttpBin {
+/// #[http(custom("/custom/endpoint/"))]
+/// fn custom_endpoint(&self);
+/// # }
+/// ```
+pub use retrofit_macros::http;
+
+/// Sets the default headers for every request.
+///
+/// # Example
+///
+/// ```
+/// # use retrofit::{service, default_headers};
+/// #[service(base_url = "https://api.github.com")]
+/// #[default_headers(accept = "application/vnd.github.mercy-preview+json")]
+/// pub trait Github {
+/// }
+/// ```
+pub use retrofit_macros::default_headers;
+
+/// Authenticates every request with the `bearer` or `basic` scheme.
+///
+/// The service builder gains a `bearer_auth(token)` or `basic_auth(username, Option<String>)` method,
+/// and a `credentials(provider)` method to supply the credentials with a [`CredentialProvider`]
+/// at request time.
+///
+/// When a request is rejected with `401 Unauthorized`, the provider could refresh the credentials,
+/// e.g. an expired OAuth2 access token, then the request is replayed once.
+///
+/// # Example
+///
+/// ```
+/// # use retrofit::{service, auth, get};
+/// #[service(base_url = "http://httpbin.org")]
+/// #[auth(bearer)]
+/// pub trait HttpBin {
+///     #[get("/headers")]
+///     fn headers(&self) -> serde_json::Value;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let httpbin = HttpBinClient::builder().bearer_auth("secret").build();
+///
+/// let res = httpbin.headers()?;
+/// assert_eq!(res["headers"]["Authorization"], "Bearer secret");
+/// # Ok(()) }
+/// ```
+///
+/// ## Refresh the credentials
+///
+/// ```
+/// # use std::sync::Mutex;
+/// # use retrofit::{service, auth, get, CredentialProvider, Credentials};
+/// #[service(base_url = "http://httpbin.org")]
+/// #[auth(basic)]
+/// pub trait HttpBin {
+///     #[get("/basic-auth/{user}/{passwd}")]
+///     fn basic_auth(&self, user: &str, passwd: &str) -> serde_json::Value;
+/// }
+///
+/// struct Expired(Mutex<&'static str>);
+///
+/// impl CredentialProvider for Expired {
+///     fn credentials(&self) -> retrofit::Result<Credentials> {
+///         Ok(Credentials::Basic {
+///             username: "user".into(),
+///             password: Some(self.0.lock().unwrap().to_string()),
+///         })
+///     }
+///
+///     fn refresh(&self) -> retrofit::Result<bool> {
+///         *self.0.lock().unwrap() = "passwd";
+///
+///         Ok(true)
+///     }
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let httpbin = HttpBinClient::builder()
+///     .credentials(Expired(Mutex::new("expired")))
+///     .build();
+///
+/// let res = httpbin.basic_auth("user", "passwd")?;
+/// assert_eq!(res["authenticated"], true);
 /// # Ok(()) }
 /// ```
-pub use retrofit_macros::get;
-
-/// Use a custom HTTP verb for a request.
 ///
-/// # Example
+/// ## Refresh the credentials asynchronously
+///
+/// The async client calls `async_credentials` and `async_refresh` of the provider,
+/// override them to fetch the credentials without blocking the executor.
 ///
 /// ```
-/// # use retrofit::{service, http};
-/// # #[service(base_url = "http://httpbin.org")]
-/// # pub trait HttpBin {
-/// #[http(custom("/custom/endpoint/"))]
-/// fn custom_endpoint(&self);
-/// # }
-/// ```
-pub use retrofit_macros::http;
-
-/// Sets the default headers for every request.
+/// # use std::sync::Mutex;
+/// # use retrofit::{service, auth, get, backend::BoxFuture, CredentialProvider, Credentials};
+/// #[service(base_url = "http://httpbin.org", async)]
+/// #[auth(bearer)]
+/// pub trait HttpBin {
+///     #[get("/bearer")]
+///     fn bearer(&self) -> serde_json::Value;
+/// }
 ///
-/// # Example
+/// struct OAuth2(Mutex<String>);
 ///
-/// ```
-/// # use retrofit::{service, default_headers};
-/// #[service(base_url = "https://api.github.com")]
-/// #[default_headers(accept = "application/vnd.github.mercy-preview+json")]
-/// pub trait Github {
+/// impl CredentialProvider for OAuth2 {
+///     fn credentials(&self) -> retrofit::Result<Credentials> {
+///         Ok(Credentials::Bearer(self.0.lock().unwrap().clone()))
+///     }
+///
+///     fn async_refresh(&self) -> BoxFuture<'_, retrofit::Result<bool>> {
+///         Box::pin(async move {
+///             // e.g. request a new access token with the refresh token
+///             *self.0.lock().unwrap() = "fresh".to_string();
+///
+///             Ok(true)
+///         })
+///     }
 /// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> retrofit::Result<()> {
+/// let httpbin = HttpBinClient::builder()
+///     .credentials(OAuth2(Mutex::new(String::new())))
+///     .build();
+///
+/// let res = httpbin.bearer().await?;
+/// assert_eq!(res["authenticated"], true);
+/// # Ok(()) }
 /// ```
-pub use retrofit_macros::default_headers;
+pub use retrofit_macros::auth;
 
 /// Adds headers literally supplied in the value.
 ///
@@ -152,6 +812,118 @@ pub use retrofit_macros::headers;
 /// # Ok(()) }
 /// ```
 ///
+/// # XML
+///
+/// Use `xml` to sets the body to the XML serialization of the passed value,
+/// and also sets the `Content-Type: application/xml` header.
+///
+/// The value is encoded with the converter of `application/xml`, which is registered by default
+/// with the `xml` feature, the root element is named after the type of the value.
+/// A method using `xml` fails to compile without the `xml` feature.
+///
+/// ## Example
+///
+#[cfg_attr(feature = "xml", doc = "```")]
+#[cfg_attr(not(feature = "xml"), doc = "```ignore")]
+/// # use serde::Serialize;
+/// # use retrofit::{service, post, request};
+/// #[derive(Serialize)]
+/// pub struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[post("/post")]
+///     #[request(xml = point)]
+///     fn post(&self, point: &Point) -> serde_json::Value;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let res = http_bin().post(&Point { x: 1, y: 2 })?;
+/// assert_eq!(res["data"], "<Point><x>1</x><y>2</y></Point>");
+/// assert_eq!(res["headers"]["Content-Type"], "application/xml");
+/// # Ok(()) }
+/// ```
+///
+/// # MessagePack and CBOR
+///
+/// Use `msgpack` or `cbor` to sets the body to the binary serialization of the passed value,
+/// and also sets the `Content-Type: application/msgpack` or `Content-Type: application/cbor` header.
+///
+/// The value is encoded with the converter of the media type,
+/// which is registered by default with the `msgpack` or `cbor` feature.
+/// A method using `msgpack` or `cbor` fails to compile without the feature.
+///
+/// ## Example
+///
+#[cfg_attr(all(feature = "msgpack", feature = "cbor"), doc = "```")]
+#[cfg_attr(not(all(feature = "msgpack", feature = "cbor")), doc = "```ignore")]
+/// # use serde::Serialize;
+/// # use retrofit::{service, post, request};
+/// #[derive(Serialize)]
+/// pub struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[post("/post")]
+///     #[request(msgpack = point)]
+///     fn msgpack(&self, point: &Point) -> serde_json::Value;
+///
+///     #[post("/post")]
+///     #[request(cbor = point)]
+///     fn cbor(&self, point: &Point) -> serde_json::Value;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let bin = http_bin();
+///
+/// let res = bin.msgpack(&Point { x: 1, y: 2 })?;
+/// assert_eq!(res["headers"]["Content-Type"], "application/msgpack");
+///
+/// let res = bin.cbor(&Point { x: 1, y: 2 })?;
+/// assert_eq!(res["headers"]["Content-Type"], "application/cbor");
+/// # Ok(()) }
+/// ```
+///
+/// # Protocol Buffers
+///
+/// Use `protobuf` to sets the body to the encoded `prost::Message`,
+/// and also sets the `Content-Type: application/x-protobuf` header, with the `protobuf` feature.
+///
+/// ## Example
+///
+#[cfg_attr(feature = "protobuf", doc = "```")]
+#[cfg_attr(not(feature = "protobuf"), doc = "```ignore")]
+/// # use retrofit::{service, post, request};
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// pub struct Repo {
+///     #[prost(string, tag = "1")]
+///     name: String,
+///     #[prost(uint32, tag = "2")]
+///     stars: u32,
+/// }
+///
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[post("/post")]
+///     #[request(protobuf = repo)]
+///     fn post(&self, repo: &Repo) -> serde_json::Value;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let repo = Repo { name: "retrofit".into(), stars: 42 };
+///
+/// let res = http_bin().post(&repo)?;
+/// assert_eq!(res["data"], "\n\u{8}retrofit\u{10}*");
+/// assert_eq!(res["headers"]["Content-Type"], "application/x-protobuf");
+/// # Ok(()) }
+/// ```
+///
 /// # Body
 ///
 /// Use `body` to sets the body to the `String`, `File` or bytes.
@@ -208,6 +980,59 @@ pub use retrofit_macros::headers;
 /// # Ok(()) }
 /// ```
 ///
+/// # Streaming body
+///
+/// A `Self::Upload` argument declared with `#[body(upload)]` streams the request body on demand.
+///
+/// The async `Upload` is built from a `Stream` of `Bytes` chunks with `Upload::new`,
+/// or from an `AsyncRead` with `Upload::from_reader`, e.g. a `tokio::fs::File`.
+/// The blocking `Upload` is built from a `Read` with `Upload::new`, e.g. a `std::fs::File`.
+///
+/// `Upload::on_progress` observes the upload, the callback is called with the bytes sent so far.
+///
+/// **Note**: A streamed body could not be cloned, so the request is neither retried nor replayed.
+///
+/// ## Example
+///
+/// ```
+/// # use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
+/// # use retrofit::{service, post, upload::Upload};
+/// #[service(base_url = "http://httpbin.org", async)]
+/// pub trait HttpBin {
+///     #[post("/post")]
+///     fn post(&self, #[body(upload)] data: Self::Upload) -> serde_json::Value;
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> retrofit::Result<()> {
+/// let chunks: Vec<std::io::Result<bytes::Bytes>> = vec![Ok("hello ".into()), Ok("world".into())];
+/// let sent = Arc::new(AtomicU64::new(0));
+/// let upload = {
+///     let sent = sent.clone();
+///     Upload::new(futures::stream::iter(chunks)).on_progress(move |n| sent.store(n, Ordering::SeqCst))
+/// };
+///
+/// let res = http_bin().post(upload).await?;
+/// assert_eq!(res["data"], "hello world");
+/// assert_eq!(sent.load(Ordering::SeqCst), 11);
+/// # Ok(()) }
+/// ```
+///
+/// Using a blocking reader:
+/// ```
+/// # use retrofit::{service, post, upload::blocking::Upload};
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[post("/post")]
+///     fn post(&self, #[body(upload)] data: Self::Upload) -> serde_json::Value;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let res = http_bin().post(Upload::new(std::io::Cursor::new("from a reader!")))?;
+/// assert_eq!(res["data"], "from a reader!");
+/// # Ok(()) }
+/// ```
+///
 /// # Multipart Form
 ///
 /// Use `multipart` to sends a `multipart/form-data` body,
@@ -234,11 +1059,53 @@ pub use retrofit_macros::headers;
 /// http_bin().post(form)?;
 /// # Ok(()) }
 /// ```
+///
+/// ## Parts
+///
+/// Declare the parts with `#[part]` or `#[part("name")]` to let the method build the form,
+/// the kind of the part follows the argument type:
+///
+/// - `text` for `str` and `String`
+/// - `bytes` for `[u8]`, `Vec<u8>` and `Bytes`, sent as `application/octet-stream`
+/// - `file` for `Path` and `PathBuf`, streamed from the file with the file name
+/// - `json` for the other types, serialized as a part of `application/json`
+///
+/// The kind could be given explicitly, e.g. `#[part("avatar", file)] avatar: &str`.
+///
+/// **Note**: The file of a `file` part is opened when the form is built, which blocks the thread
+/// of an `async` method, while its content is streamed asynchronously.
+///
+/// ```
+/// # use retrofit::{service, post};
+/// #[derive(serde::Serialize)]
+/// pub struct Profile {
+///     age: u32,
+/// }
+///
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[post("/post")]
+///     fn upload(
+///         &self,
+///         #[part] username: &str,
+///         #[part("data")] bytes: Vec<u8>,
+///         #[part] profile: &Profile,
+///     ) -> serde_json::Value;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let res = http_bin().upload("seanmonstar", b"hello".to_vec(), &Profile { age: 42 })?;
+/// assert_eq!(res["form"]["username"], "seanmonstar");
+/// assert_eq!(res["form"]["data"], "hello");
+/// assert_eq!(res["form"]["profile"], r#"{"age":42}"#);
+/// # Ok(()) }
+/// ```
 pub use retrofit_macros::request;
 
 /// Decode response to a submitted request.
 ///
-/// **Notes**: By default, attempts are made to decode the returned content using the JSON encoding.
+/// **Notes**: By default, the returned content is decoded with the converter of its `Content-Type`,
+/// or as JSON, see [converters](attr.service.html#converters).
 ///
 /// # JSON
 ///
@@ -313,6 +1180,116 @@ pub use retrofit_macros::request;
 /// # Ok(()) }
 /// ```
 ///
+/// # XML
+///
+/// Use `xml()` to deserialize the response body as XML with the converter of `application/xml`,
+/// which is registered by default with the `xml` feature, and fails to compile without it.
+///
+/// The text is decoded like `text()`, or `xml("charset")` gives the default encoding
+/// of a response without the charset parameter of `Content-Type`, like `text_with_charset`.
+///
+/// ## Example
+///
+#[cfg_attr(feature = "xml", doc = "```")]
+#[cfg_attr(not(feature = "xml"), doc = "```ignore")]
+/// # use serde::Deserialize;
+/// # use retrofit::{service, get, response};
+/// #[derive(Debug, Deserialize)]
+/// pub struct Slideshow {
+///     #[serde(rename = "@title")]
+///     title: String,
+/// }
+///
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[get("/xml")]
+///     #[response(xml())]
+///     fn xml(&self) -> Slideshow;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let slideshow = http_bin().xml()?;
+/// assert_eq!(slideshow.title, "Sample Slide Show");
+/// # Ok(()) }
+/// ```
+///
+/// # MessagePack and CBOR
+///
+/// Use `msgpack()` or `cbor()` to deserialize the response body with the converter of
+/// `application/msgpack` or `application/cbor`, which is registered by default with the feature
+/// of the same name, regardless of the `Content-Type` of the response,
+/// and fails to compile without the feature.
+///
+/// ## Example
+///
+#[cfg_attr(all(feature = "msgpack", feature = "cbor"), doc = "```no_run")]
+#[cfg_attr(not(all(feature = "msgpack", feature = "cbor")), doc = "```ignore")]
+/// # use serde::{Deserialize, Serialize};
+/// # use retrofit::{service, post, request, response};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// pub struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// #[service(base_url = "https://internal.example.com")]
+/// pub trait Points {
+///     #[post("/points")]
+///     #[request(msgpack = point)]
+///     #[response(msgpack())]
+///     fn create(&self, point: &Point) -> Point;
+///
+///     #[post("/points")]
+///     #[request(cbor = point)]
+///     #[response(cbor())]
+///     fn create_cbor(&self, point: &Point) -> Point;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let point = points().create(&Point { x: 1, y: 2 })?;
+/// assert_eq!(point.x, 1);
+/// # Ok(()) }
+/// ```
+///
+/// # Protocol Buffers
+///
+/// Use `protobuf()` to decode the response body as a `prost::Message`, with the `protobuf` feature.
+///
+/// The request is sent with the `Accept: application/x-protobuf` header,
+/// and a response of another `Content-Type` is returned as `Error::Deserialize`.
+///
+/// ## Example
+///
+#[cfg_attr(feature = "protobuf", doc = "```no_run")]
+#[cfg_attr(not(feature = "protobuf"), doc = "```ignore")]
+/// # use retrofit::{service, post, request, response};
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// pub struct Repo {
+///     #[prost(string, tag = "1")]
+///     name: String,
+///     #[prost(uint32, tag = "2")]
+///     stars: u32,
+/// }
+///
+/// #[service(base_url = "https://internal.example.com")]
+/// pub trait Repos {
+///     #[post("/repos")]
+///     #[request(protobuf = repo)]
+///     #[response(protobuf())]
+///     fn create(&self, repo: &Repo) -> Repo;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let repo = repos().create(&Repo { name: "retrofit".into(), stars: 0 })?;
+/// assert_eq!(repo.name, "retrofit");
+/// # Ok(()) }
+/// ```
+///
+/// # Errors
+///
+/// Use `error = ApiError` to decode the body of a non-success response as `Error::Api`,
+/// it may be combined with the body decoder, e.g. `#[response(text(), error = ApiError)]`.
+///
 /// # Binary
 ///
 /// Use `bytes()` to get the full response body as `Bytes`.
@@ -334,4 +1311,182 @@ pub use retrofit_macros::request;
 /// assert_eq!(res.len(), 8);
 /// # Ok(()) }
 /// ```
+///
+/// # NDJSON
+///
+/// Use `ndjson()` with a method returning `Ndjson<T>` to decode each line of a newline-delimited
+/// JSON body as it is received, e.g. a watch or a log tail, instead of buffering the body
+/// like `bytes()`. The blank lines are skipped.
+///
+/// The blocking `Ndjson` is an `Iterator` of `Result<T>`, the async `Ndjson` is a `Stream`
+/// of `Result<T>`, where a line which could not be decoded is returned as `Error::Deserialize`.
+///
+/// ## Example
+///
+/// ```
+/// # use serde::Deserialize;
+/// # use retrofit::{service, get, response};
+/// #[derive(Debug, Deserialize)]
+/// pub struct Event {
+///     id: u32,
+/// }
+///
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[get("/stream/{n}")]
+///     #[response(ndjson())]
+///     fn stream(&self, n: usize) -> Ndjson<Event>;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// for (idx, event) in http_bin().stream(3)?.enumerate() {
+///     assert_eq!(event?.id, idx as u32);
+/// }
+/// # Ok(()) }
+/// ```
+///
+/// # Headers
+///
+/// Use `header = "name"` to return the header parsed with `FromStr` instead of the body,
+/// or `Option<T>` if the header may be missing.
+///
+/// A method returning a tuple gets the decoded body first, followed by the headers
+/// in the order of the `header = "..."` options, e.g. `(Repo, String)` with `header = "etag"`.
+/// Use `()` as the body to return several headers only, e.g. `((), String, Option<u64>)`,
+/// a tuple without the body fails to compile.
+///
+/// ## Example
+///
+/// ```
+/// # use retrofit::{service, get, response};
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[get("/response-headers")]
+///     #[response(header = "x-ratelimit-remaining")]
+///     fn remaining(&self, #[query("X-RateLimit-Remaining")] remaining: u32) -> u32;
+///
+///     #[get("/response-headers")]
+///     #[response(header = "etag", header = "x-ratelimit-reset")]
+///     fn etag(&self, #[query("ETag")] etag: &str) -> (serde_json::Value, String, Option<u64>);
+///
+///     #[get("/response-headers")]
+///     #[response(header = "etag", header = "x-ratelimit-reset")]
+///     fn etag_only(&self, #[query("ETag")] etag: &str) -> ((), String, Option<u64>);
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// let bin = http_bin();
+/// assert_eq!(bin.remaining(42)?, 42);
+///
+/// let (body, etag, reset) = bin.etag("abc")?;
+/// assert_eq!(body["ETag"], "abc");
+/// assert_eq!(etag, "abc");
+/// assert_eq!(reset, None);
+///
+/// let ((), etag, _) = bin.etag_only("abc")?;
+/// assert_eq!(etag, "abc");
+/// # Ok(()) }
+/// ```
+///
+/// The headers of a tuple without the body are ambiguous:
+///
+/// ```compile_fail
+/// # use retrofit::{service, get, response};
+/// #[service(base_url = "http://httpbin.org")]
+/// pub trait HttpBin {
+///     #[get("/response-headers")]
+///     #[response(header = "etag", header = "x-ratelimit-reset")]
+///     fn etag(&self, #[query("ETag")] etag: &str) -> (String, Option<u64>);
+/// }
+/// ```
 pub use retrofit_macros::response;
+
+/// Retry the request on the transport errors and the listed status codes.
+///
+/// - `max` the maximum retries, `3` by default.
+/// - `backoff` the delay strategy, `"none"`, `"constant"` or `"exponential"` (default).
+/// - `delay_ms` the initial delay in milliseconds, `100` by default.
+/// - `on` the status codes to retry on, `[502, 503, 504]` by default.
+/// - `non_idempotent` allows retrying a `POST` or `PATCH` request.
+///
+/// The `Retry-After` header of the response takes precedence over the backoff delay.
+///
+/// A service-level default policy could be declared with `#[service(retry(...))]`,
+/// or `#[service(retry)]` for the defaults above,
+/// which only applies to the idempotent requests, e.g. `GET`, `HEAD`, `PUT`, `DELETE` and `OPTIONS`,
+/// unless `non_idempotent` is given.
+///
+/// # Example
+///
+/// ```
+/// # use retrofit::{service, get, retry};
+/// #[service(base_url = "http://httpbin.org", retry(max = 2, backoff = "constant"))]
+/// pub trait HttpBin {
+///     #[get("/status/{code}")]
+///     fn status(&self, code: u16);
+///
+///     #[get("/status/{code}")]
+///     #[retry(max = 3, backoff = "exponential", delay_ms = 10, on = [502, 503, 504])]
+///     fn unstable(&self, code: u16);
+/// }
+///
+/// let err = http_bin().unstable(503).unwrap_err();
+/// assert_eq!(err.status().unwrap(), 503);
+/// ```
+pub use retrofit_macros::retry;
+
+/// Returns the items of every page, which follows the next page on demand.
+///
+/// The method should return `Paginate<T>`, which is an `Iterator` of `Result<T>`,
+/// or a `Stream` of `Result<T>` with an `async` service. Every page is decoded as JSON.
+///
+/// - `#[paginate(link_header)]` follows the `rel="next"` link of the `Link` header,
+///   every page is a JSON array. The pages stop at a link to the current page,
+///   and a link to another origin is returned as `Error::Request`.
+/// - `#[paginate(cursor(query = "cursor", next = "/next", items = "/items"))]` sets the `query` parameter
+///   to the cursor at the `next` JSON pointer of the page, the items are at the `items` JSON pointer.
+/// - `#[paginate(offset(offset = "offset", limit = "limit", size = 30))]` sets the `offset` and `limit`
+///   query parameters, every page is a JSON array, the last page has less than `size` items.
+///
+/// The options above are the defaults, e.g. `#[paginate(cursor(query = "after"))]`.
+///
+/// # Example
+///
+/// ```no_run
+/// # use retrofit::{service, get, paginate};
+/// #[service(base_url = "https://api.github.com")]
+/// pub trait Github {
+///     #[get("/users/{user}/repos")]
+///     #[paginate(link_header)]
+///     fn repos(&self, user: &str, #[query] per_page: u32) -> Paginate<serde_json::Value>;
+/// }
+///
+/// # fn main() -> retrofit::Result<()> {
+/// for repo in github().repos("flier", 100) {
+///     println!("{}", repo?["full_name"]);
+/// }
+/// # Ok(()) }
+/// ```
+///
+/// ## Async
+///
+/// ```no_run
+/// # use futures::StreamExt;
+/// # use retrofit::{service, get, paginate};
+/// #[service(base_url = "https://api.example.com", async)]
+/// pub trait Events {
+///     #[get("/events")]
+///     #[paginate(cursor(query = "after", next = "/meta/next_cursor", items = "/data"))]
+///     fn events(&self) -> Paginate<serde_json::Value>;
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> retrofit::Result<()> {
+/// let mut events = events().events();
+///
+/// while let Some(event) = events.next().await {
+///     println!("{}", event?);
+/// }
+/// # Ok(()) }
+/// ```
+pub use retrofit_macros::paginate;